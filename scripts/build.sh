@@ -6,4 +6,5 @@ export RUSTFLAGS="-D warnings"
 
 cargo build --verbose &&
 cargo test --verbose &&
+cargo test --verbose --all-features &&
 cargo doc
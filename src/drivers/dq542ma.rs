@@ -15,7 +15,8 @@ use embedded_hal::digital::blocking::OutputPin;
 use fugit::NanosDurationU32 as Nanoseconds;
 
 use crate::traits::{
-    EnableDirectionControl, EnableStepControl, SetDirection, Step as StepTrait,
+    ConfiguredMicrosteps, EnableDirectionControl, EnableStepControl,
+    SetDirection, Step as StepTrait,
 };
 
 /// The DQ542MA driver API
@@ -23,10 +24,14 @@ use crate::traits::{
 /// Users are not expected to use this API directly, except to create an
 /// instance using [`DQ542MA::new`]. Please check out
 /// [`Stepper`](crate::Stepper) instead.
+///
+/// The step mode of the DQ542MA is selected using DIP switches. Use
+/// [`DQ542MA::set_configured_microsteps`] to tell the driver about it.
 pub struct DQ542MA<Enable, Step, Dir> {
     enable: Enable,
     step: Step,
     dir: Dir,
+    microsteps: Option<u16>,
 }
 
 impl DQ542MA<(), (), ()> {
@@ -36,10 +41,35 @@ impl DQ542MA<(), (), ()> {
             enable: (),
             step: (),
             dir: (),
+            microsteps: None,
         }
     }
 }
 
+impl<Enable, Step, Dir> DQ542MA<Enable, Step, Dir> {
+    /// Provide the number of microsteps per full step set by the DIP switches
+    ///
+    /// This lets [`Stepper::microsteps_per_rev`] and the methods based on it
+    /// work. It doesn't change the driver configuration in any way.
+    ///
+    /// [`Stepper::microsteps_per_rev`]: crate::Stepper::microsteps_per_rev
+    pub fn set_configured_microsteps(&mut self, microsteps: u16) {
+        self.microsteps = Some(microsteps);
+    }
+}
+
+impl<Enable, Step, Dir> ConfiguredMicrosteps for DQ542MA<Enable, Step, Dir> {
+    fn configured_microsteps(&self) -> Option<u16> {
+        self.microsteps
+    }
+}
+
+impl Default for DQ542MA<(), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<Step, Dir, OutputPinError> EnableDirectionControl<Dir>
     for DQ542MA<(), Step, ()>
 where
@@ -52,6 +82,7 @@ where
             enable: self.enable,
             step: self.step,
             dir,
+            microsteps: self.microsteps,
         }
     }
 }
@@ -82,6 +113,7 @@ where
             enable: self.enable,
             step,
             dir: self.dir,
+            microsteps: self.microsteps,
         }
     }
 }
@@ -100,3 +132,26 @@ where
         Ok(&mut self.step)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{motor::MotorSpec, Direction, Stepper};
+
+    use super::DQ542MA;
+
+    #[test]
+    fn configured_microsteps_should_be_used_for_conversions() {
+        let mut stepper = Stepper::from_driver(DQ542MA::new());
+        stepper.set_motor_spec(MotorSpec::new(200));
+        assert_eq!(stepper.microsteps_per_rev(), None);
+
+        // DIP switches set to 25 microsteps per full step
+        stepper.driver_mut().set_configured_microsteps(25);
+
+        assert_eq!(stepper.microsteps_per_rev(), Some(5000));
+        assert_eq!(
+            stepper.steps_for_distance(0.5, 1.0),
+            Some((2500, Direction::Forward))
+        );
+    }
+}
@@ -17,8 +17,8 @@ use fugit::NanosDurationU32 as Nanoseconds;
 use crate::{
     step_mode::StepMode32,
     traits::{
-        EnableDirectionControl, EnableStepControl, EnableStepModeControl,
-        SetDirection, SetStepMode, Step as StepTrait,
+        ConfiguredMicrosteps, EnableDirectionControl, EnableStepControl,
+        EnableStepModeControl, SetDirection, SetStepMode, Step as StepTrait,
     },
 };
 
@@ -38,6 +38,7 @@ pub struct DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
     mode2: Mode2,
     step: Step,
     dir: Dir,
+    step_mode: Option<StepMode32>,
 }
 
 impl DRV8825<(), (), (), (), (), (), (), (), ()> {
@@ -53,10 +54,50 @@ impl DRV8825<(), (), (), (), (), (), (), (), ()> {
             mode2: (),
             step: (),
             dir: (),
+            step_mode: None,
+        }
+    }
+
+    /// Create a new instance of `DRV8825`, with RESET and SLEEP tied together
+    ///
+    /// Many breakout boards connect both pins, so they're controlled through a
+    /// single pin. Pass that pin here. Step mode control can then be enabled
+    /// by passing only the MODE pins to [`Stepper::enable_step_mode_control`].
+    ///
+    /// Since resetting the driver also puts it to sleep, changing the step
+    /// mode waits for the driver to wake up again. See
+    /// [`DRV8825::WAKE_TIME`].
+    ///
+    /// [`Stepper::enable_step_mode_control`]: crate::Stepper::enable_step_mode_control
+    pub fn with_tied_reset_sleep<ResetSleep>(
+        reset_sleep: ResetSleep,
+    ) -> DRV8825<(), (), TiedToReset, ResetSleep, (), (), (), (), ()> {
+        DRV8825 {
+            enable: (),
+            fault: (),
+            sleep: TiedToReset,
+            reset: reset_sleep,
+            mode0: (),
+            mode1: (),
+            mode2: (),
+            step: (),
+            dir: (),
+            step_mode: None,
         }
     }
 }
 
+/// Marks the SLEEP pin of a [`DRV8825`] as tied to its RESET pin
+///
+/// See [`DRV8825::with_tied_reset_sleep`].
+pub struct TiedToReset;
+
+impl Default for DRV8825<(), (), (), (), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
     EnableStepModeControl<(Reset, Mode0, Mode1, Mode2)>
     for DRV8825<(), (), (), (), (), (), (), Step, Dir>
@@ -83,6 +124,7 @@ where
             mode2,
             step: self.step,
             dir: self.dir,
+            step_mode: self.step_mode,
         }
     }
 }
@@ -110,18 +152,80 @@ where
         // Reset the device's internal logic and disable the h-bridge drivers.
         self.reset.set_low()?;
 
-        use PinState::*;
-        use StepMode32::*;
-        let (mode0, mode1, mode2) = match step_mode {
-            Full => (Low, Low, Low),
-            M2 => (High, Low, Low),
-            M4 => (Low, High, Low),
-            M8 => (High, High, Low),
-            M16 => (Low, Low, High),
-            M32 => (High, High, High),
-        };
+        // Set mode signals.
+        let (mode0, mode1, mode2) = mode_signals(step_mode);
+        self.mode0.set_state(mode0)?;
+        self.mode1.set_state(mode1)?;
+        self.mode2.set_state(mode2)?;
+
+        Ok(())
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        self.reset.set_high()
+    }
+
+    fn step_mode_set(&mut self, step_mode: Self::StepMode) {
+        self.step_mode = Some(step_mode);
+    }
+}
+
+impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
+    EnableStepModeControl<(Mode0, Mode1, Mode2)>
+    for DRV8825<(), (), TiedToReset, Reset, (), (), (), Step, Dir>
+where
+    Reset: OutputPin<Error = OutputPinError>,
+    Mode0: OutputPin<Error = OutputPinError>,
+    Mode1: OutputPin<Error = OutputPinError>,
+    Mode2: OutputPin<Error = OutputPinError>,
+{
+    type WithStepModeControl =
+        DRV8825<(), (), TiedToReset, Reset, Mode0, Mode1, Mode2, Step, Dir>;
+
+    fn enable_step_mode_control(
+        self,
+        (mode0, mode1, mode2): (Mode0, Mode1, Mode2),
+    ) -> Self::WithStepModeControl {
+        DRV8825 {
+            enable: self.enable,
+            fault: self.fault,
+            sleep: self.sleep,
+            reset: self.reset,
+            mode0,
+            mode1,
+            mode2,
+            step: self.step,
+            dir: self.dir,
+            step_mode: self.step_mode,
+        }
+    }
+}
+
+impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> SetStepMode
+    for DRV8825<(), (), TiedToReset, Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Reset: OutputPin<Error = OutputPinError>,
+    Mode0: OutputPin<Error = OutputPinError>,
+    Mode1: OutputPin<Error = OutputPinError>,
+    Mode2: OutputPin<Error = OutputPinError>,
+{
+    // 7.6 Timing Requirements (page 7)
+    // https://www.ti.com/lit/ds/symlink/drv8825.pdf
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(650);
+    const HOLD_TIME: Nanoseconds = Self::WAKE_TIME;
+
+    type Error = OutputPinError;
+    type StepMode = StepMode32;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        // Reset the device's internal logic and put it to sleep.
+        self.reset.set_low()?;
 
         // Set mode signals.
+        let (mode0, mode1, mode2) = mode_signals(step_mode);
         self.mode0.set_state(mode0)?;
         self.mode1.set_state(mode1)?;
         self.mode2.set_state(mode2)?;
@@ -132,16 +236,55 @@ where
     fn enable_driver(&mut self) -> Result<(), Self::Error> {
         self.reset.set_high()
     }
+
+    fn step_mode_set(&mut self, step_mode: Self::StepMode) {
+        self.step_mode = Some(step_mode);
+    }
+}
+
+impl<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+    ConfiguredMicrosteps
+    for DRV8825<Enable, Fault, Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
+{
+    fn configured_microsteps(&self) -> Option<u16> {
+        self.step_mode.map(Into::into)
+    }
 }
 
 impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
+    DRV8825<(), (), TiedToReset, Reset, Mode0, Mode1, Mode2, Step, Dir>
+where
+    Reset: OutputPin<Error = OutputPinError>,
+{
+    /// The time the driver needs to wake up from sleep, before it can step
+    // 7.6 Timing Requirements (page 7)
+    // https://www.ti.com/lit/ds/symlink/drv8825.pdf
+    pub const WAKE_TIME: Nanoseconds = Nanoseconds::from_ticks(1_700_000);
+
+    /// Put the driver to sleep
+    ///
+    /// The h-bridges are disabled, and, since RESET is tied to SLEEP, the
+    /// driver's internal logic is reset.
+    pub fn sleep(&mut self) -> Result<(), OutputPinError> {
+        self.reset.set_low()
+    }
+
+    /// Wake the driver up
+    ///
+    /// Wait for [`Self::WAKE_TIME`], before making the next step.
+    pub fn wake(&mut self) -> Result<(), OutputPinError> {
+        self.reset.set_high()
+    }
+}
+
+impl<Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
     EnableDirectionControl<Dir>
-    for DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, ()>
+    for DRV8825<(), (), Sleep, Reset, Mode0, Mode1, Mode2, Step, ()>
 where
     Dir: OutputPin<Error = OutputPinError>,
 {
     type WithDirectionControl =
-        DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>;
+        DRV8825<(), (), Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
 
     fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
         DRV8825 {
@@ -154,12 +297,13 @@ where
             mode2: self.mode2,
             step: self.step,
             dir,
+            step_mode: self.step_mode,
         }
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> SetDirection
-    for DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>
+impl<Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> SetDirection
+    for DRV8825<(), (), Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
 where
     Dir: OutputPin<Error = OutputPinError>,
 {
@@ -175,14 +319,14 @@ where
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
+impl<Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError>
     EnableStepControl<Step>
-    for DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, (), Dir>
+    for DRV8825<(), (), Sleep, Reset, Mode0, Mode1, Mode2, (), Dir>
 where
     Step: OutputPin<Error = OutputPinError>,
 {
     type WithStepControl =
-        DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>;
+        DRV8825<(), (), Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>;
 
     fn enable_step_control(self, step: Step) -> Self::WithStepControl {
         DRV8825 {
@@ -195,12 +339,13 @@ where
             mode2: self.mode2,
             step,
             dir: self.dir,
+            step_mode: self.step_mode,
         }
     }
 }
 
-impl<Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> StepTrait
-    for DRV8825<(), (), (), Reset, Mode0, Mode1, Mode2, Step, Dir>
+impl<Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir, OutputPinError> StepTrait
+    for DRV8825<(), (), Sleep, Reset, Mode0, Mode1, Mode2, Step, Dir>
 where
     Step: OutputPin<Error = OutputPinError>,
 {
@@ -215,3 +360,74 @@ where
         Ok(&mut self.step)
     }
 }
+
+fn mode_signals(step_mode: StepMode32) -> (PinState, PinState, PinState) {
+    use PinState::*;
+    use StepMode32::*;
+    match step_mode {
+        Full => (Low, Low, Low),
+        M2 => (High, Low, Low),
+        M4 => (Low, High, Low),
+        M8 => (High, High, Low),
+        M16 => (Low, Low, High),
+        M32 => (High, High, High),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        step_mode::StepMode32,
+        test_utils::{MockPin, MockTimer},
+        Stepper,
+    };
+
+    use super::DRV8825;
+
+    #[test]
+    fn should_support_all_step_modes_up_to_32() {
+        let stepper = Stepper::from_driver(DRV8825::new())
+            .enable_step_mode_control(
+                (
+                    MockPin::new(),
+                    MockPin::new(),
+                    MockPin::new(),
+                    MockPin::new(),
+                ),
+                StepMode32::Full,
+                &mut MockTimer::new(),
+            )
+            .unwrap();
+
+        let modes: Vec<u16> =
+            stepper.supported_step_modes().map(Into::into).collect();
+        assert_eq!(modes, [1, 2, 4, 8, 16, 32]);
+    }
+
+    #[test]
+    fn tied_reset_sleep_should_wait_for_wake_up_after_mode_change() {
+        let reset_sleep = MockPin::new();
+        let mode0 = MockPin::new();
+        let mut timer = MockTimer::new();
+        let durations = timer.durations.clone();
+
+        let mut stepper = Stepper::from_driver(DRV8825::with_tied_reset_sleep(
+            reset_sleep.clone(),
+        ))
+        .enable_step_mode_control(
+            (mode0.clone(), MockPin::new(), MockPin::new()),
+            StepMode32::M2,
+            &mut timer,
+        )
+        .unwrap();
+
+        assert_eq!(reset_sleep.is_high(), Some(true));
+        assert_eq!(mode0.is_high(), Some(true));
+        assert_eq!(durations.borrow().last().unwrap().ticks(), 1_700);
+
+        stepper.driver_mut().sleep().unwrap();
+        assert_eq!(reset_sleep.is_high(), Some(false));
+        stepper.driver_mut().wake().unwrap();
+        assert_eq!(reset_sleep.is_high(), Some(true));
+    }
+}
@@ -11,14 +11,17 @@
 
 use core::convert::Infallible;
 
-use embedded_hal::digital::{blocking::OutputPin, PinState};
+use embedded_hal::digital::{
+    blocking::{InputPin, OutputPin},
+    PinState,
+};
 use fugit::NanosDurationU32 as Nanoseconds;
 
 use crate::{
     step_mode::StepMode256,
     traits::{
-        EnableDirectionControl, EnableStepControl, EnableStepModeControl,
-        SetDirection, SetStepMode, Step,
+        ConfiguredMicrosteps, EnableDirectionControl, EnableStepControl,
+        EnableStepModeControl, ReadyStatus, SetDirection, SetStepMode, Step,
     },
 };
 
@@ -41,6 +44,7 @@ pub struct STSPIN220<
     mode2: Mode2,
     step_mode3: StepMode3,
     dir_mode4: DirMode4,
+    step_mode: Option<StepMode256>,
 }
 
 impl STSPIN220<(), (), (), (), (), ()> {
@@ -53,10 +57,49 @@ impl STSPIN220<(), (), (), (), (), ()> {
             mode2: (),
             step_mode3: (),
             dir_mode4: (),
+            step_mode: None,
         }
     }
 }
 
+impl<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+    STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+{
+    /// Create a new instance of `STSPIN220` with all pins at once
+    ///
+    /// This is a shortcut for the common case of controlling all of the
+    /// driver's capabilities from software. It's equivalent to calling
+    /// [`STSPIN220::new`], followed by enabling step mode control, direction
+    /// control, and step control.
+    ///
+    /// Please note that this doesn't configure the step mode or the direction.
+    /// Set those through [`Stepper`](crate::Stepper) before stepping.
+    pub fn with_pins(
+        enable_fault: EnableFault,
+        standby_reset: StandbyReset,
+        mode1: Mode1,
+        mode2: Mode2,
+        step_mode3: StepMode3,
+        dir_mode4: DirMode4,
+    ) -> Self {
+        Self {
+            enable_fault,
+            standby_reset,
+            mode1,
+            mode2,
+            step_mode3,
+            dir_mode4,
+            step_mode: None,
+        }
+    }
+}
+
+impl Default for STSPIN220<(), (), (), (), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<
         EnableFault,
         StandbyReset,
@@ -88,6 +131,7 @@ where
             mode2,
             step_mode3: self.step_mode3,
             dir_mode4: self.dir_mode4,
+            step_mode: self.step_mode,
         }
     }
 }
@@ -149,6 +193,19 @@ where
         // Leave standby mode.
         self.standby_reset.set_high()
     }
+
+    fn step_mode_set(&mut self, step_mode: Self::StepMode) {
+        self.step_mode = Some(step_mode);
+    }
+}
+
+impl<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+    ConfiguredMicrosteps
+    for STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+{
+    fn configured_microsteps(&self) -> Option<u16> {
+        self.step_mode.map(Into::into)
+    }
 }
 
 impl<
@@ -178,6 +235,7 @@ where
             mode2: self.mode2,
             step_mode3: self.step_mode3,
             dir_mode4,
+            step_mode: self.step_mode,
         }
     }
 }
@@ -232,6 +290,7 @@ where
             mode2: self.mode2,
             step_mode3,
             dir_mode4: self.dir_mode4,
+            step_mode: self.step_mode,
         }
     }
 }
@@ -258,3 +317,152 @@ where
         Ok(&mut self.step_mode3)
     }
 }
+
+impl<
+        EnableFault,
+        StandbyReset,
+        Mode1,
+        Mode2,
+        StepMode3,
+        DirMode4,
+        InputPinError,
+    > ReadyStatus
+    for STSPIN220<EnableFault, StandbyReset, Mode1, Mode2, StepMode3, DirMode4>
+where
+    EnableFault: InputPin<Error = InputPinError>,
+{
+    type Error = InputPinError;
+
+    /// Read the EN/FAULT pin
+    ///
+    /// The STSPIN220 pulls EN/FAULT low while it is disabled or reports a
+    /// fault. Please note that standby is controlled through STBY/RESET, which
+    /// is not reflected by this pin.
+    fn is_ready(&mut self) -> Result<bool, Self::Error> {
+        self.enable_fault.is_high()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ramp_maker::Flat;
+
+    use crate::{
+        step_mode::StepMode256,
+        test_utils::{MockInputPin, MockPin, MockTimer, Ticks},
+        traits::ReadyStatus as _,
+        Stepper,
+    };
+
+    use super::STSPIN220;
+
+    #[test]
+    fn with_pins_should_create_fully_configured_driver() {
+        let standby_reset = MockPin::new();
+        let mode1 = MockPin::new();
+        let mode2 = MockPin::new();
+        let step_mode3 = MockPin::new();
+        let dir_mode4 = MockPin::new();
+
+        let mut stepper = Stepper::from_driver(STSPIN220::with_pins(
+            (),
+            standby_reset.clone(),
+            mode1.clone(),
+            mode2.clone(),
+            step_mode3.clone(),
+            dir_mode4.clone(),
+        ));
+        stepper
+            .set_step_mode(StepMode256::M8, &mut MockTimer::new())
+            .wait()
+            .unwrap();
+
+        assert_eq!(standby_reset.is_high(), Some(true));
+        assert_eq!(mode1.is_high(), Some(true));
+        assert_eq!(mode2.is_high(), Some(true));
+        assert_eq!(step_mode3.is_high(), Some(true));
+        assert_eq!(dir_mode4.is_high(), Some(false));
+    }
+
+    #[test]
+    fn is_ready_should_follow_enable_fault_pin() {
+        let enable_fault = MockInputPin::new(false);
+
+        let mut driver = STSPIN220::with_pins(
+            enable_fault.clone(),
+            MockPin::new(),
+            MockPin::new(),
+            MockPin::new(),
+            MockPin::new(),
+            MockPin::new(),
+        );
+        assert_eq!(driver.is_ready(), Ok(false));
+
+        enable_fault.set_high(true);
+        assert_eq!(driver.is_ready(), Ok(true));
+    }
+
+    #[test]
+    fn should_support_all_step_modes_up_to_256() {
+        let stepper = Stepper::from_driver(STSPIN220::with_pins(
+            (),
+            MockPin::new(),
+            MockPin::new(),
+            MockPin::new(),
+            MockPin::new(),
+            MockPin::new(),
+        ));
+
+        let modes: Vec<u16> =
+            stepper.supported_step_modes().map(Into::into).collect();
+        assert_eq!(modes, [1, 2, 4, 8, 16, 32, 64, 128, 256]);
+    }
+
+    #[test]
+    fn microstep_up_should_rescale_position() {
+        let mode1 = MockPin::new();
+        let mode2 = MockPin::new();
+
+        let mut stepper = Stepper::from_driver(STSPIN220::with_pins(
+            (),
+            MockPin::new(),
+            mode1.clone(),
+            mode2.clone(),
+            MockPin::new(),
+            MockPin::new(),
+        ))
+        .enable_motion_control((MockTimer::new(), Flat::new(), Ticks));
+
+        stepper
+            .driver_mut()
+            .set_step_mode(StepMode256::M4)
+            .unwrap()
+            .wait()
+            .unwrap();
+        stepper.move_to_position(0.001, 3).wait().unwrap();
+
+        stepper
+            .driver_mut()
+            .microstep_up()
+            .unwrap()
+            .unwrap()
+            .wait()
+            .unwrap();
+
+        assert_eq!(stepper.driver().step_mode(), Some(StepMode256::M8));
+        assert_eq!(stepper.driver().current_step(), 6);
+        // MODE1/MODE2 are both high for M8, but not for M4.
+        assert_eq!(mode1.is_high(), Some(true));
+        assert_eq!(mode2.is_high(), Some(true));
+
+        stepper
+            .driver_mut()
+            .microstep_down()
+            .unwrap()
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert_eq!(stepper.driver().step_mode(), Some(StepMode256::M4));
+        assert_eq!(stepper.driver().current_step(), 3);
+    }
+}
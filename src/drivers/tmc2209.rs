@@ -0,0 +1,465 @@
+//! TMC2209 Driver
+//!
+//! Platform-agnostic driver API for the TMC2209 stepper motor driver. Can be
+//! used on any platform for which implementations of the required
+//! [embedded-hal] traits are available.
+//!
+//! The driver is configured over its single-wire UART interface, instead of
+//! dedicated mode pins. Only register writes are supported, so the UART's RX
+//! line doesn't need to be connected.
+//!
+//! The UART is accessed through the blocking [`serial::blocking::Write`]
+//! trait, like the pins of the other drivers are accessed through blocking
+//! traits. Each register write is only eight bytes long, and the traits of this
+//! crate, like [`SetStepMode`], are synchronous, so there would be nothing to
+//! gain from an asynchronous UART.
+//!
+//! The EN pin is not controlled by this driver. Tie it low, or control it
+//! directly, outside of this driver.
+//!
+//! For the most part, users are not expected to use this API directly. Please
+//! check out [`Stepper`](crate::Stepper) instead.
+//!
+//! [embedded-hal]: https://crates.io/crates/embedded-hal
+//! [`serial::blocking::Write`]: embedded_hal::serial::blocking::Write
+
+use core::convert::Infallible;
+
+use embedded_hal::{
+    digital::blocking::OutputPin,
+    serial::{blocking::Write, ErrorType},
+};
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    step_mode::StepMode256,
+    traits::{
+        ConfiguredMicrosteps, EnableDirectionControl, EnableStepControl,
+        EnableStepModeControl, SetDirection, SetStepMode, Step as StepTrait,
+    },
+};
+
+/// Global configuration register
+const GCONF: u8 = 0x00;
+
+/// Driver current control register
+const IHOLD_IRUN: u8 = 0x10;
+
+/// Chopper configuration register
+const CHOPCONF: u8 = 0x6c;
+
+/// GCONF: Use the internal current reference, instead of VREF
+///
+/// Also disables the PDN_UART pin's power-down function, selects the step mode
+/// through MRES instead of the MS1/MS2 pins, and enables the step pulse filter.
+const GCONF_BASE: u32 = 0x0000_01c0;
+
+/// GCONF: Use spreadCycle instead of stealthChop
+const GCONF_EN_SPREAD_CYCLE: u32 = 1 << 2;
+
+/// CHOPCONF reset value, with MRES set to 256 microsteps
+const CHOPCONF_RESET: u32 = 0x1000_0053;
+
+/// The position of the MRES field within CHOPCONF
+const CHOPCONF_MRES_SHIFT: u32 = 24;
+
+/// Full-scale sense voltage in millivolts, with VSENSE cleared
+const V_FS: u64 = 325;
+
+/// The TMC2209 driver API
+///
+/// Users are not expected to use this API directly, except to create an
+/// instance using [`TMC2209::new`]. Please check out
+/// [`Stepper`](crate::Stepper) instead.
+///
+/// The UART is passed to [`Stepper::enable_step_mode_control`]. It must be
+/// configured for 8N1, at a baud rate the TMC2209 can detect.
+///
+/// [`Stepper::enable_step_mode_control`]: crate::Stepper::enable_step_mode_control
+pub struct TMC2209<Uart, Step, Dir> {
+    uart: Uart,
+    step: Step,
+    dir: Dir,
+    address: u8,
+    sense_resistor: u16,
+    gconf: u32,
+    chopconf: u32,
+    step_mode: Option<StepMode256>,
+}
+
+impl TMC2209<(), (), ()> {
+    /// Create a new instance of `TMC2209`
+    ///
+    /// Assumes UART address 0 and a sense resistor of 110 mΩ, which is common
+    /// on breakout boards. Use [`TMC2209::with_address`] and
+    /// [`TMC2209::with_sense_resistor`] to override those.
+    pub fn new() -> Self {
+        Self {
+            uart: (),
+            step: (),
+            dir: (),
+            address: 0,
+            sense_resistor: 110,
+            gconf: GCONF_BASE,
+            chopconf: CHOPCONF_RESET,
+            step_mode: None,
+        }
+    }
+}
+
+impl Default for TMC2209<(), (), ()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Uart, Step, Dir> TMC2209<Uart, Step, Dir> {
+    /// Set the UART address, as selected by the MS1/MS2 pins
+    ///
+    /// Only the lower two bits are used.
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address & 0b11;
+        self
+    }
+
+    /// Set the value of the sense resistors, in milliohms
+    ///
+    /// This is used by [`TMC2209::set_run_current`] to compute the current
+    /// scale.
+    pub fn with_sense_resistor(mut self, milliohms: u16) -> Self {
+        self.sense_resistor = milliohms;
+        self
+    }
+}
+
+impl<Uart, Step, Dir> TMC2209<Uart, Step, Dir>
+where
+    Uart: Write,
+{
+    /// Select stealthChop (`true`) or spreadCycle (`false`)
+    ///
+    /// stealthChop is the quieter mode, and the driver's default.
+    pub fn set_stealth_chop(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), <Uart as ErrorType>::Error> {
+        if enabled {
+            self.gconf &= !GCONF_EN_SPREAD_CYCLE;
+        } else {
+            self.gconf |= GCONF_EN_SPREAD_CYCLE;
+        }
+        self.write_register(GCONF, self.gconf)
+    }
+
+    /// Set the RMS motor current while moving, in milliamps
+    ///
+    /// The standstill current is set to half of that. The result is clamped to
+    /// what the sense resistors allow. Requires the internal current
+    /// reference, which is selected when setting the step mode, or by
+    /// [`TMC2209::set_stealth_chop`].
+    pub fn set_run_current(
+        &mut self,
+        milliamps: u16,
+    ) -> Result<(), <Uart as ErrorType>::Error> {
+        let irun = current_scale(milliamps, self.sense_resistor);
+        let ihold = irun / 2;
+        let ihold_delay: u8 = 1;
+
+        self.write_register(
+            IHOLD_IRUN,
+            u32::from(ihold)
+                | u32::from(irun) << 8
+                | u32::from(ihold_delay) << 16,
+        )
+    }
+
+    fn write_register(
+        &mut self,
+        register: u8,
+        data: u32,
+    ) -> Result<(), <Uart as ErrorType>::Error> {
+        self.uart
+            .write(&write_datagram(self.address, register, data))
+    }
+}
+
+impl<Uart, Step, Dir> EnableStepModeControl<Uart> for TMC2209<(), Step, Dir>
+where
+    Uart: Write,
+{
+    type WithStepModeControl = TMC2209<Uart, Step, Dir>;
+
+    fn enable_step_mode_control(self, uart: Uart) -> Self::WithStepModeControl {
+        TMC2209 {
+            uart,
+            step: self.step,
+            dir: self.dir,
+            address: self.address,
+            sense_resistor: self.sense_resistor,
+            gconf: self.gconf,
+            chopconf: self.chopconf,
+            step_mode: self.step_mode,
+        }
+    }
+}
+
+impl<Uart, Step, Dir> SetStepMode for TMC2209<Uart, Step, Dir>
+where
+    Uart: Write,
+{
+    // The new step mode applies as soon as the register has been written.
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(0);
+
+    type Error = <Uart as ErrorType>::Error;
+    type StepMode = StepMode256;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        use StepMode256::*;
+        let mres = match step_mode {
+            Full => 8,
+            M2 => 7,
+            M4 => 6,
+            M8 => 5,
+            M16 => 4,
+            M32 => 3,
+            M64 => 2,
+            M128 => 1,
+            M256 => 0,
+        };
+
+        // Make sure MRES is used, instead of the MS1/MS2 pins.
+        self.write_register(GCONF, self.gconf)?;
+
+        self.chopconf = self.chopconf & !(0xf << CHOPCONF_MRES_SHIFT)
+            | mres << CHOPCONF_MRES_SHIFT;
+        self.write_register(CHOPCONF, self.chopconf)
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        // The driver keeps running while the registers are written.
+        Ok(())
+    }
+
+    fn step_mode_set(&mut self, step_mode: Self::StepMode) {
+        self.step_mode = Some(step_mode);
+    }
+}
+
+impl<Uart, Step, Dir> ConfiguredMicrosteps for TMC2209<Uart, Step, Dir> {
+    fn configured_microsteps(&self) -> Option<u16> {
+        self.step_mode.map(Into::into)
+    }
+}
+
+impl<Uart, Step, Dir, OutputPinError> EnableDirectionControl<Dir>
+    for TMC2209<Uart, Step, ()>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    type WithDirectionControl = TMC2209<Uart, Step, Dir>;
+
+    fn enable_direction_control(self, dir: Dir) -> Self::WithDirectionControl {
+        TMC2209 {
+            uart: self.uart,
+            step: self.step,
+            dir,
+            address: self.address,
+            sense_resistor: self.sense_resistor,
+            gconf: self.gconf,
+            chopconf: self.chopconf,
+            step_mode: self.step_mode,
+        }
+    }
+}
+
+impl<Uart, Step, Dir, OutputPinError> SetDirection for TMC2209<Uart, Step, Dir>
+where
+    Dir: OutputPin<Error = OutputPinError>,
+{
+    // Datasheet, section 13.1: tDSU
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(20);
+
+    type Dir = Dir;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        Ok(&mut self.dir)
+    }
+}
+
+impl<Uart, Step, Dir, OutputPinError> EnableStepControl<Step>
+    for TMC2209<Uart, (), Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    type WithStepControl = TMC2209<Uart, Step, Dir>;
+
+    fn enable_step_control(self, step: Step) -> Self::WithStepControl {
+        TMC2209 {
+            uart: self.uart,
+            step,
+            dir: self.dir,
+            address: self.address,
+            sense_resistor: self.sense_resistor,
+            gconf: self.gconf,
+            chopconf: self.chopconf,
+            step_mode: self.step_mode,
+        }
+    }
+}
+
+impl<Uart, Step, Dir, OutputPinError> StepTrait for TMC2209<Uart, Step, Dir>
+where
+    Step: OutputPin<Error = OutputPinError>,
+{
+    // Datasheet, section 13.1: tSH
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(100);
+
+    type Step = Step;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+/// Build the datagram that writes `data` to a register
+fn write_datagram(address: u8, register: u8, data: u32) -> [u8; 8] {
+    let [d0, d1, d2, d3] = data.to_be_bytes();
+    let mut datagram = [0x05, address, register | 0x80, d0, d1, d2, d3, 0];
+    datagram[7] = crc(&datagram[..7]);
+    datagram
+}
+
+/// Compute the CRC of a datagram, as specified in datasheet section 4.2
+fn crc(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in bytes {
+        let mut byte = byte;
+        for _ in 0..8 {
+            if (crc >> 7) ^ (byte & 0x01) != 0 {
+                crc = (crc << 1) ^ 0x07;
+            } else {
+                crc <<= 1;
+            }
+            byte >>= 1;
+        }
+    }
+    crc
+}
+
+/// Compute the current scale (IRUN/IHOLD) for an RMS current
+///
+/// Datasheet, section 9: I_RMS = (CS + 1) / 32 * V_FS / (R_SENSE + 20 mΩ) / √2
+fn current_scale(milliamps: u16, sense_resistor: u16) -> u8 {
+    let numerator =
+        32 * 1414 * u64::from(milliamps) * (u64::from(sense_resistor) + 20);
+    let denominator = V_FS * 1000 * 1000;
+
+    let cs_plus_one = numerator / denominator;
+    cs_plus_one.clamp(1, 32) as u8 - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use embedded_hal::serial::{blocking::Write, ErrorType};
+
+    use crate::{
+        step_mode::StepMode256, test_utils::MockTimer,
+        traits::EnableStepModeControl as _, Stepper,
+    };
+
+    use super::{crc, current_scale, TMC2209};
+
+    /// A UART that records everything written to it
+    struct MockUart(Vec<u8>);
+
+    impl ErrorType for MockUart {
+        type Error = Infallible;
+    }
+
+    impl Write for MockUart {
+        fn write(&mut self, buffer: &[u8]) -> Result<(), Self::Error> {
+            self.0.extend_from_slice(buffer);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn crc_should_match_datasheet_example() {
+        // Read access to GCONF on address 0
+        assert_eq!(crc(&[0x05, 0x00, 0x00]), 0x48);
+    }
+
+    #[test]
+    fn set_step_mode_should_write_gconf_and_chopconf() {
+        let mut timer = MockTimer::new();
+        let mut stepper = Stepper::from_driver(TMC2209::new())
+            .enable_step_mode_control(
+                MockUart(Vec::new()),
+                StepMode256::M16,
+                &mut timer,
+            )
+            .unwrap();
+        let written = &mut stepper.driver_mut().uart.0;
+
+        let gconf = [0x05, 0x00, 0x80, 0x00, 0x00, 0x01, 0xc0, 0xf6];
+        let chopconf = [0x05, 0x00, 0xec, 0x14, 0x00, 0x00, 0x53, 0x52];
+        assert_eq!(written[..8], gconf);
+        assert_eq!(written[8..], chopconf);
+        written.clear();
+
+        stepper
+            .set_step_mode(StepMode256::Full, &mut timer)
+            .wait()
+            .unwrap();
+
+        let chopconf = [0x05, 0x00, 0xec, 0x18, 0x00, 0x00, 0x53, 0xfb];
+        assert_eq!(stepper.driver().uart.0[8..], chopconf);
+    }
+
+    #[test]
+    fn set_stealth_chop_should_toggle_spread_cycle() {
+        let mut driver = TMC2209::new()
+            .with_address(2)
+            .enable_step_mode_control(MockUart(Vec::new()));
+
+        driver.set_stealth_chop(false).unwrap();
+        driver.set_stealth_chop(true).unwrap();
+
+        let spread_cycle = [0x05, 0x02, 0x80, 0x00, 0x00, 0x01, 0xc4, 0x60];
+        let stealth_chop = [0x05, 0x02, 0x80, 0x00, 0x00, 0x01, 0xc0, 0x80];
+        assert_eq!(driver.uart.0[..8], spread_cycle);
+        assert_eq!(driver.uart.0[8..], stealth_chop);
+    }
+
+    #[test]
+    fn set_run_current_should_write_ihold_irun() {
+        let mut driver =
+            TMC2209::new().enable_step_mode_control(MockUart(Vec::new()));
+
+        driver.set_run_current(1000).unwrap();
+
+        // IRUN = 17, IHOLD = 8, IHOLDDELAY = 1
+        assert_eq!(
+            driver.uart.0,
+            [0x05, 0x00, 0x90, 0x00, 0x01, 0x11, 0x08, 0x35]
+        );
+    }
+
+    #[test]
+    fn current_scale_should_be_clamped() {
+        assert_eq!(current_scale(0, 110), 0);
+        assert_eq!(current_scale(5000, 110), 31);
+    }
+}
@@ -10,6 +10,7 @@
 //! - [DRV8825](crate::drivers::drv8825::DRV8825)
 //! - [STSPIN220](crate::drivers::stspin220::STSPIN220)
 //! - [DQ542MA](crate::drivers::dq542ma::DQ542MA)
+//! - TMC2209, if the `tmc2209` feature is enabled
 //!
 //! Please check out the documentation of [`Stepper`], which is the main entry
 //! point to this API.
@@ -154,8 +155,9 @@
 //!
 //! [RampMaker]: https://crates.io/crates/ramp-maker
 
-#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![deny(missing_docs, rustdoc::broken_intra_doc_links)]
+#![allow(clippy::type_complexity)]
 
 pub extern crate embedded_hal;
 pub extern crate fugit;
@@ -163,17 +165,23 @@ pub extern crate ramp_maker;
 
 pub mod compat;
 pub mod drivers;
+pub mod microstepping;
 pub mod motion_control;
+pub mod motor;
 pub mod step_mode;
 pub mod traits;
 pub mod util;
 
 mod stepper;
 
+#[cfg(test)]
+mod test_utils;
+
 pub use self::stepper::*;
 
 /// Defines the direction in which to rotate the motor
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Direction {
     /// Rotate the motor forward
     ///
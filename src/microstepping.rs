@@ -0,0 +1,116 @@
+//! Math for microstepping drivers that are built from discrete H-bridges
+//!
+//! Integrated driver chips take care of microstepping internally. If you drive
+//! a bipolar motor using two H-bridges with PWM-capable inputs, you need to
+//! generate the coil currents yourself. See [`microstep_table`].
+
+use core::f32::consts::FRAC_PI_2;
+
+/// Generate the coil duty cycles for each microstep of an electrical cycle
+///
+/// `N` is the number of microsteps per electrical cycle. An electrical cycle
+/// consists of 4 full steps, so for 1/16 microstepping, `N` is 64.
+///
+/// Each entry is a pair of PWM duty cycles, where `255` means full current.
+/// The first value of the pair is for coil A, which follows a sine, the second
+/// is for coil B, which follows a cosine.
+///
+/// The table contains the magnitudes of the coil currents only. The polarity
+/// depends on the quadrant of the electrical cycle the microstep is in, and
+/// needs to be applied through the H-bridge's direction inputs:
+///
+/// | Quadrant (`4 * i / N`) | Coil A   | Coil B   |
+/// |------------------------|----------|----------|
+/// | 0                      | positive | positive |
+/// | 1                      | positive | negative |
+/// | 2                      | negative | negative |
+/// | 3                      | negative | positive |
+///
+/// The table is computed without relying on floating-point functions from
+/// `std`, so this works on `no_std` targets. It is still a good idea to
+/// generate it once and keep it around, instead of calling this function for
+/// every step.
+pub fn microstep_table<const N: usize>() -> [(u8, u8); N] {
+    let mut table = [(0, 0); N];
+
+    for (i, entry) in table.iter_mut().enumerate() {
+        // Figure out the quadrant and the angle within the quadrant using
+        // integer math. That way, the quadrant boundaries are exact.
+        let quarters = 4 * i;
+        let quadrant = quarters / N;
+        let angle = (quarters % N) as f32 / N as f32 * FRAC_PI_2;
+
+        let (sin, cos) = (sin(angle), cos(angle));
+
+        // In odd quadrants, the magnitudes of sine and cosine are swapped,
+        // relative to the angle within the quadrant.
+        *entry = match quadrant {
+            0 | 2 => (duty(sin), duty(cos)),
+            _ => (duty(cos), duty(sin)),
+        };
+    }
+
+    table
+}
+
+/// Sine, for `x` in `0..=π/2`
+///
+/// Uses the Taylor series, which is more than accurate enough in this range.
+fn sin(x: f32) -> f32 {
+    let x2 = x * x;
+    x * (1.0
+        - x2 / 6.0 * (1.0 - x2 / 20.0 * (1.0 - x2 / 42.0 * (1.0 - x2 / 72.0))))
+}
+
+/// Cosine, for `x` in `0..=π/2`
+///
+/// Uses the Taylor series, which is more than accurate enough in this range.
+fn cos(x: f32) -> f32 {
+    let x2 = x * x;
+    1.0 - x2 / 2.0
+        * (1.0
+            - x2 / 12.0
+                * (1.0 - x2 / 30.0 * (1.0 - x2 / 56.0 * (1.0 - x2 / 90.0))))
+}
+
+/// Quantize a value in `0.0..=1.0` into a duty cycle
+fn duty(value: f32) -> u8 {
+    let duty = value * u8::MAX as f32 + 0.5;
+
+    if duty >= u8::MAX as f32 {
+        u8::MAX
+    } else if duty <= 0.0 {
+        0
+    } else {
+        duty as u8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::microstep_table;
+
+    #[test]
+    fn microstep_table_should_approximate_sine_and_cosine() {
+        let table = microstep_table::<16>();
+
+        // Quadrant boundaries
+        assert_eq!(table[0], (0, 255));
+        assert_eq!(table[4], (255, 0));
+        assert_eq!(table[8], (0, 255));
+        assert_eq!(table[12], (255, 0));
+
+        // Half-way through each quadrant, both coils carry the same current.
+        for &i in &[2, 6, 10, 14] {
+            assert_eq!(table[i], (180, 180));
+        }
+
+        for (i, &(a, b)) in table.iter().enumerate() {
+            let angle = i as f32 / 16.0 * 2.0 * core::f32::consts::PI;
+            let expected_a = (angle.sin().abs() * 255.0).round() as u8;
+            let expected_b = (angle.cos().abs() * 255.0).round() as u8;
+
+            assert_eq!((a, b), (expected_a, expected_b), "microstep {}", i);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+/// The configuration of [`SoftwareMotionControl`], as plain data
+///
+/// Bundles the settings that can otherwise be made through individual
+/// methods, so they can be applied at once using
+/// [`SoftwareMotionControl::configure`], and read back using
+/// [`SoftwareMotionControl::config`]. This is useful for loading the motion
+/// configuration of a machine from flash memory.
+///
+/// Enable the `serde` feature to make this type serializable.
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+/// [`SoftwareMotionControl::configure`]: super::SoftwareMotionControl::configure
+/// [`SoftwareMotionControl::config`]: super::SoftwareMotionControl::config
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MotionConfig<Velocity> {
+    /// The minimum and maximum time between two steps, in nanoseconds
+    ///
+    /// See [`SoftwareMotionControl::set_step_interval_bounds`].
+    ///
+    /// [`SoftwareMotionControl::set_step_interval_bounds`]: super::SoftwareMotionControl::set_step_interval_bounds
+    pub step_interval_bounds: Option<(u32, u32)>,
+
+    /// Whether to skip the setup time after setting the direction
+    ///
+    /// See [`SoftwareMotionControl::set_skip_dir_setup_time`].
+    ///
+    /// [`SoftwareMotionControl::set_skip_dir_setup_time`]: super::SoftwareMotionControl::set_skip_dir_setup_time
+    pub skip_dir_setup_time: bool,
+
+    /// The highest velocity that motions may be started with
+    ///
+    /// See [`SoftwareMotionControl::set_velocity_limit`].
+    ///
+    /// [`SoftwareMotionControl::set_velocity_limit`]: super::SoftwareMotionControl::set_velocity_limit
+    pub velocity_limit: Option<Velocity>,
+
+    /// The number of steps and the velocity of the approach creep
+    ///
+    /// See [`SoftwareMotionControl::set_approach_creep`].
+    ///
+    /// [`SoftwareMotionControl::set_approach_creep`]: super::SoftwareMotionControl::set_approach_creep
+    pub approach_creep: Option<(u32, Velocity)>,
+
+    /// The velocity range the motor must not cruise in
+    ///
+    /// See [`SoftwareMotionControl::set_resonance_band`].
+    ///
+    /// [`SoftwareMotionControl::set_resonance_band`]: super::SoftwareMotionControl::set_resonance_band
+    pub resonance_band: Option<(Velocity, Velocity)>,
+}
@@ -1,3 +1,7 @@
+use core::convert::Infallible;
+
+use crate::SignalError;
+
 /// An error that can occur while using [`SoftwareMotionControl`]
 ///
 /// [`SoftwareMotionControl`]: super::SoftwareMotionControl
@@ -12,21 +16,95 @@ pub enum Error<
 > {
     /// Error while setting direction
     SetDirection(
-        crate::SignalError<
-            SetDirectionPinUnavailable,
-            SetDirectionError,
-            TimerError,
-        >,
+        SignalError<SetDirectionPinUnavailable, SetDirectionError, TimerError>,
     ),
 
     /// Error while stepping the motor
-    Step(crate::SignalError<StepPinUnavailable, StepError, TimerError>),
+    Step(SignalError<StepPinUnavailable, StepError, TimerError>),
 
     /// Error while converting between time formats
     TimeConversion(TimeConversionError<DelayToTicksError>),
 
     /// Error while waiting for a step to finish
     StepDelay(TimerError),
+
+    /// The requested velocity exceeds the limit
+    ///
+    /// See [`SoftwareMotionControl::set_velocity_limit`].
+    ///
+    /// [`SoftwareMotionControl::set_velocity_limit`]: super::SoftwareMotionControl::set_velocity_limit
+    VelocityExceeded,
+
+    /// The requested motion has more steps than a single motion can make
+    ///
+    /// Positions are tracked as `i32`, so a single motion can't be longer than
+    /// `i32::MAX` steps. Longer motions need to be split up.
+    MoveTooLong,
+}
+
+impl<SetDirectionError, StepError, TimerError>
+    Error<
+        Infallible,
+        SetDirectionError,
+        Infallible,
+        StepError,
+        TimerError,
+        Infallible,
+    >
+{
+    /// Convert into an error type that leaves out impossible variants
+    ///
+    /// For many drivers, accessing the pins can't fail, and neither can the
+    /// conversion from delay values into timer ticks. In that case, this
+    /// method converts this error into a [`SimpleError`], which can be matched
+    /// on without handling variants that can never occur.
+    pub fn simplify(
+        self,
+    ) -> SimpleError<SetDirectionError, StepError, TimerError> {
+        match self {
+            Self::SetDirection(err) => match err {
+                SignalError::PinUnavailable(err) => match err {},
+                SignalError::Pin(err) => SimpleError::SetDirection(err),
+                SignalError::Timer(err) => SimpleError::Timer(err),
+            },
+            Self::Step(err) => match err {
+                SignalError::PinUnavailable(err) => match err {},
+                SignalError::Pin(err) => SimpleError::Step(err),
+                SignalError::Timer(err) => SimpleError::Timer(err),
+            },
+            Self::TimeConversion(TimeConversionError::DelayToTicks(err)) => {
+                match err {}
+            }
+            Self::StepDelay(err) => SimpleError::Timer(err),
+            Self::VelocityExceeded => SimpleError::VelocityExceeded,
+            Self::MoveTooLong => SimpleError::MoveTooLong,
+        }
+    }
+}
+
+/// A simplified version of [`Error`]
+///
+/// See [`Error::simplify`].
+#[derive(Debug, Eq, PartialEq)]
+pub enum SimpleError<SetDirectionError, StepError, TimerError> {
+    /// Error while setting the DIR signal
+    SetDirection(SetDirectionError),
+
+    /// Error while setting the STEP signal
+    Step(StepError),
+
+    /// Error while using the timer
+    Timer(TimerError),
+
+    /// The requested velocity exceeds the limit
+    ///
+    /// See [`Error::VelocityExceeded`].
+    VelocityExceeded,
+
+    /// The requested motion has more steps than a single motion can make
+    ///
+    /// See [`Error::MoveTooLong`].
+    MoveTooLong,
 }
 
 /// An error occurred while converting between time formats
@@ -48,3 +126,44 @@ pub enum BusyError<T> {
     /// Another error has occurred
     Other(T),
 }
+
+/// A named position could not be registered or moved to
+#[derive(Debug, Eq, PartialEq)]
+pub enum PositionError<T> {
+    /// The index is out of range, or no position has been registered for it
+    InvalidIndex,
+
+    /// Another error has occurred
+    Other(T),
+}
+
+/// The acceleration time passed to [`SoftwareMotionControl::set_accel_time`]
+/// is invalid
+///
+/// This happens, if the time is zero, or if it can't be represented by the
+/// motion profile's number type.
+///
+/// [`SoftwareMotionControl::set_accel_time`]: super::SoftwareMotionControl::set_accel_time
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct InvalidAccelTime;
+
+/// The motor is not at the position it was last commanded to move to
+///
+/// Returned by [`SoftwareMotionControl::assert_arrived`].
+///
+/// [`SoftwareMotionControl::assert_arrived`]: super::SoftwareMotionControl::assert_arrived
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PositionMismatch {
+    /// The target of the last motion
+    pub expected: i32,
+
+    /// The current step
+    pub actual: i32,
+}
+
+impl PositionMismatch {
+    /// The number of steps that are missing to reach the target
+    pub fn discrepancy(&self) -> i32 {
+        self.expected - self.actual
+    }
+}
@@ -0,0 +1,123 @@
+use crate::traits::{ConfiguredMicrosteps, MotionControl};
+
+/// Stores errors from [`MotionControl::update`], instead of returning them
+///
+/// When motion control is updated from an interrupt handler, there's often no
+/// good way to handle an error right there. This wrapper takes the first error
+/// returned by the wrapped driver's `update` method and keeps it, until it is
+/// retrieved using [`ErrorLatch::take_error`], for example by a supervisor
+/// task running outside of the interrupt handler.
+///
+/// After an error has been latched, `update` returns `Ok(false)`, so the
+/// caller stops updating the motion. Calling `update` again retries the
+/// failed operation. Additional errors are discarded, while an error is still
+/// latched.
+///
+/// Errors from [`MotionControl::move_to_position`],
+/// [`MotionControl::move_by`], [`MotionControl::retarget`], and
+/// [`MotionControl::reset_position`] are returned as usual.
+///
+/// You can use `ErrorLatch` directly, but it is designed to be used through
+/// [`Stepper::latch_errors`].
+///
+/// [`Stepper::latch_errors`]: crate::Stepper::latch_errors
+pub struct ErrorLatch<Driver: MotionControl> {
+    driver: Driver,
+    error: Option<Driver::Error>,
+}
+
+impl<Driver> ErrorLatch<Driver>
+where
+    Driver: MotionControl,
+{
+    /// Create a new instance of `ErrorLatch`
+    pub fn new(driver: Driver) -> Self {
+        Self {
+            driver,
+            error: None,
+        }
+    }
+
+    /// Access a reference to the wrapped driver
+    pub fn inner(&self) -> &Driver {
+        &self.driver
+    }
+
+    /// Access a mutable reference to the wrapped driver
+    pub fn inner_mut(&mut self) -> &mut Driver {
+        &mut self.driver
+    }
+
+    /// Release the wrapped driver
+    ///
+    /// Any latched error is dropped.
+    pub fn release(self) -> Driver {
+        self.driver
+    }
+
+    /// Retrieve the latched error, if any, and clear the latch
+    pub fn take_error(&mut self) -> Option<Driver::Error> {
+        self.error.take()
+    }
+}
+
+impl<Driver> MotionControl for ErrorLatch<Driver>
+where
+    Driver: MotionControl,
+{
+    type Velocity = Driver::Velocity;
+    type Error = Driver::Error;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.driver.move_to_position(max_velocity, target_step)
+    }
+
+    fn move_by(
+        &mut self,
+        max_velocity: Self::Velocity,
+        steps: i32,
+    ) -> Result<(), Self::Error> {
+        self.driver.move_by(max_velocity, steps)
+    }
+
+    fn retarget(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.driver.retarget(max_velocity, target_step)
+    }
+
+    fn current_position(&self) -> Result<i32, Self::Error> {
+        self.driver.current_position()
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        self.driver.reset_position(step)
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        match self.driver.update() {
+            Ok(still_moving) => Ok(still_moving),
+            Err(err) => {
+                if self.error.is_none() {
+                    self.error = Some(err);
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
+impl<Driver> ConfiguredMicrosteps for ErrorLatch<Driver>
+where
+    Driver: MotionControl + ConfiguredMicrosteps,
+{
+    fn configured_microsteps(&self) -> Option<u16> {
+        self.driver.configured_microsteps()
+    }
+}
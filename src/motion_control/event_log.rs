@@ -0,0 +1,206 @@
+use crate::traits::{ConfiguredMicrosteps, MotionControl};
+
+/// An event recorded by [`EventLog`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// A motion to the given step was started
+    MoveStarted {
+        /// The step the motion is going to end at
+        target_step: i32,
+    },
+
+    /// A motion by the given number of steps was started
+    MoveByStarted {
+        /// The number of steps to move, relative to the current position
+        steps: i32,
+    },
+
+    /// The position was reset to the given step
+    PositionReset {
+        /// The new position
+        step: i32,
+    },
+
+    /// [`MotionControl::update`] returned an error
+    UpdateFailed,
+
+    /// The motion has finished
+    MoveFinished,
+}
+
+/// Records motion events in a fixed-capacity ring buffer
+///
+/// Wraps a driver that implements [`MotionControl`] and keeps track of the
+/// motions started through it, and how they ended. Unlike a callback, this
+/// doesn't require any code to run while the motion is being updated. A
+/// supervisor task can retrieve the events later, using
+/// [`EventLog::drain_events`].
+///
+/// The log holds up to `N` events. If it's full, the oldest event is
+/// overwritten. All calls are passed on to the wrapped driver unchanged.
+///
+/// Events are not timestamped, and phases of a motion, like setting the
+/// direction or making a step, are not logged. `EventLog` only sees the
+/// [`MotionControl`] interface of the wrapped driver, which provides neither a
+/// clock nor those phases. Use [`SoftwareMotionControl::on_state_change`] to
+/// observe the phases of the software fallback.
+///
+/// You can use `EventLog` directly, but it is designed to be used through
+/// [`Stepper::log_events`].
+///
+/// [`Stepper::log_events`]: crate::Stepper::log_events
+/// [`SoftwareMotionControl::on_state_change`]: super::SoftwareMotionControl::on_state_change
+pub struct EventLog<Driver, const N: usize> {
+    driver: Driver,
+    events: [Option<Event>; N],
+    start: usize,
+    len: usize,
+    moving: bool,
+}
+
+impl<Driver, const N: usize> EventLog<Driver, N> {
+    /// Create a new instance of `EventLog`
+    pub fn new(driver: Driver) -> Self {
+        Self {
+            driver,
+            events: [None; N],
+            start: 0,
+            len: 0,
+            moving: false,
+        }
+    }
+
+    /// Access a reference to the wrapped driver
+    pub fn inner(&self) -> &Driver {
+        &self.driver
+    }
+
+    /// Access a mutable reference to the wrapped driver
+    pub fn inner_mut(&mut self) -> &mut Driver {
+        &mut self.driver
+    }
+
+    /// Release the wrapped driver
+    ///
+    /// Any events that haven't been drained are dropped.
+    pub fn release(self) -> Driver {
+        self.driver
+    }
+
+    /// Move the logged events into `out`, oldest first
+    ///
+    /// Returns the number of events written, as there might be fewer events
+    /// than `out` has room for. Only that many elements at the start of `out`
+    /// are overwritten. Events that don't fit into `out` stay in the log.
+    pub fn drain_events(&mut self, out: &mut [Event]) -> usize {
+        let mut written = 0;
+
+        for slot in out.iter_mut().take(self.len) {
+            if let Some(event) = self.events[self.start].take() {
+                *slot = event;
+                written += 1;
+            }
+            self.start = (self.start + 1) % N;
+        }
+        self.len -= written;
+
+        written
+    }
+
+    fn log(&mut self, event: Event) {
+        if N == 0 {
+            return;
+        }
+
+        if self.len == N {
+            // The log is full. Overwrite the oldest event.
+            self.events[self.start] = Some(event);
+            self.start = (self.start + 1) % N;
+        } else {
+            self.events[(self.start + self.len) % N] = Some(event);
+            self.len += 1;
+        }
+    }
+}
+
+impl<Driver, const N: usize> MotionControl for EventLog<Driver, N>
+where
+    Driver: MotionControl,
+{
+    type Velocity = Driver::Velocity;
+    type Error = Driver::Error;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.driver.move_to_position(max_velocity, target_step)?;
+
+        self.log(Event::MoveStarted { target_step });
+        self.moving = true;
+
+        Ok(())
+    }
+
+    fn move_by(
+        &mut self,
+        max_velocity: Self::Velocity,
+        steps: i32,
+    ) -> Result<(), Self::Error> {
+        self.driver.move_by(max_velocity, steps)?;
+
+        self.log(Event::MoveByStarted { steps });
+        self.moving = true;
+
+        Ok(())
+    }
+
+    fn retarget(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.driver.retarget(max_velocity, target_step)?;
+
+        self.log(Event::MoveStarted { target_step });
+        self.moving = true;
+
+        Ok(())
+    }
+
+    fn current_position(&self) -> Result<i32, Self::Error> {
+        self.driver.current_position()
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        self.driver.reset_position(step)?;
+        self.log(Event::PositionReset { step });
+        Ok(())
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        match self.driver.update() {
+            Ok(still_moving) => {
+                if self.moving && !still_moving {
+                    self.log(Event::MoveFinished);
+                    self.moving = false;
+                }
+                Ok(still_moving)
+            }
+            Err(err) => {
+                self.log(Event::UpdateFailed);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl<Driver, const N: usize> ConfiguredMicrosteps for EventLog<Driver, N>
+where
+    Driver: ConfiguredMicrosteps,
+{
+    fn configured_microsteps(&self) -> Option<u16> {
+        self.driver.configured_microsteps()
+    }
+}
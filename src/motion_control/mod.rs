@@ -2,32 +2,66 @@
 //!
 //! See [`SoftwareMotionControl`] for more information.
 
+mod config;
 mod conversion;
 mod error;
+mod error_latch;
+mod event_log;
+mod snapshot;
 mod state;
+mod switch_step_mode;
+mod track_direction;
 
 pub use self::{
+    config::MotionConfig,
     conversion::DelayToTicks,
-    error::{BusyError, Error, TimeConversionError},
+    error::{
+        BusyError, Error, InvalidAccelTime, PositionError, PositionMismatch,
+        SimpleError, TimeConversionError,
+    },
+    error_latch::ErrorLatch,
+    event_log::{Event, EventLog},
+    snapshot::MotionState,
+    switch_step_mode::SwitchStepModeFuture,
+    track_direction::TrackDirection,
 };
 
-use core::convert::Infallible;
+use core::{
+    convert::{Infallible, TryFrom as _},
+    mem, ops,
+};
 
-use embedded_hal::digital::ErrorType;
-use fugit::NanosDurationU32 as Nanoseconds;
+use embedded_hal::digital::{blocking::OutputPin as _, ErrorType};
+use fugit::{
+    NanosDurationU32 as Nanoseconds, TimerDurationU32 as TimerDuration,
+};
 use fugit_timer::Timer as TimerTrait;
-use ramp_maker::MotionProfile;
+use ramp_maker::{util::traits::Sqrt, MotionProfile, Trapezoidal};
 use replace_with::replace_with_and_return;
 
 use crate::{
+    step_mode::StepMode,
     traits::{
-        EnableMotionControl, MotionControl, SetDirection, SetStepMode, Step,
+        ConfiguredMicrosteps, EnableMotionControl, MotionControl, SetDirection,
+        SetStepMode, Step,
     },
     util::ref_mut::RefMut,
-    Direction, SetDirectionFuture, SetStepModeFuture, StepFuture,
+    Direction, SetDirectionFuture, SetStepModeFuture, SignalError, StepFuture,
 };
 
-use self::state::State;
+use self::state::{Config, State, Timing};
+
+/// The number of named positions [`SoftwareMotionControl`] can store
+///
+/// See [`SoftwareMotionControl::register_position`].
+pub const MAX_POSITIONS: usize = 16;
+
+// The largest number of steps a single motion can make. Positions are tracked
+// as `i32`, so this is the largest distance from the current position that the
+// direction and remaining steps of a motion can be computed for. The motion
+// profiles and the delay conversion don't limit it any further, as RampMaker
+// accepts any `u32` number of steps, and delays are converted per step.
+const MAX_MOVE_STEPS: u32 = i32::MAX as u32;
 
 /// Software implementation of motion control capability
 ///
@@ -53,6 +87,18 @@ pub struct SoftwareMotionControl<
     current_step: i32,
     current_direction: Direction,
     convert: Convert,
+    resonance_band: Option<(Profile::Velocity, Profile::Velocity)>,
+    positions: [Option<i32>; MAX_POSITIONS],
+    velocity_limit: Option<Profile::Velocity>,
+    config: Config,
+    target_step: Option<i32>,
+    start_step: Option<i32>,
+    approach_creep: Option<(u32, Profile::Velocity)>,
+    approach: Option<(Profile::Velocity, i32)>,
+    velocity: Option<Profile::Velocity>,
+    continuous: Option<Profile::Velocity>,
+    prepared: Option<(i32, Direction)>,
+    timing: Option<Timing<TIMER_HZ>>,
 }
 
 impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>
@@ -85,6 +131,23 @@ where
             // that point.
             current_direction: Direction::Forward,
             convert,
+            resonance_band: None,
+            positions: [None; MAX_POSITIONS],
+            velocity_limit: None,
+            config: Config {
+                interval_bounds: None,
+                skip_dir_setup_time: false,
+                armed: false,
+                on_state_change: None,
+            },
+            target_step: None,
+            start_step: None,
+            approach_creep: None,
+            approach: None,
+            velocity: None,
+            continuous: None,
+            prepared: None,
+            timing: None,
         }
     }
 
@@ -152,6 +215,358 @@ where
         self.current_direction
     }
 
+    /// The current step, including a step that is currently being made
+    ///
+    /// A step that is being made will be counted once it's finished. Motions
+    /// need to take it into account already, or they'll end up one step off.
+    fn committed_step(&self) -> i32 {
+        let mut current_step = self.current_step;
+        if let State::Step { .. } = self.state {
+            current_step =
+                current_step.wrapping_add(self.current_direction as i32);
+        }
+        current_step
+    }
+
+    /// Apply the resonance band to `velocity`, then check it against the limit
+    ///
+    /// Returns `None`, if the resulting velocity exceeds the limit.
+    fn limit_velocity(
+        &self,
+        velocity: Profile::Velocity,
+    ) -> Option<Profile::Velocity>
+    where
+        Profile::Velocity: Copy + PartialOrd,
+    {
+        let velocity = match self.resonance_band {
+            Some((low, high)) if low <= velocity && velocity < high => high,
+            _ => velocity,
+        };
+
+        match self.velocity_limit {
+            Some(limit) if velocity > limit => None,
+            _ => Some(velocity),
+        }
+    }
+
+    /// Plan a motion from the current step to `target_step`
+    fn start_motion(
+        &mut self,
+        max_velocity: Profile::Velocity,
+        target_step: i32,
+    ) {
+        let steps_from_here = target_step - self.committed_step();
+
+        self.profile
+            .enter_position_mode(max_velocity, steps_from_here.unsigned_abs());
+
+        let direction = if steps_from_here > 0 {
+            Direction::Forward
+        } else {
+            Direction::Backward
+        };
+        self.new_motion = Some(direction);
+    }
+
+    /// Access the step mode the wrapped driver is configured for
+    ///
+    /// This is queried from the wrapped driver, see [`ConfiguredMicrosteps`].
+    /// Returns `None`, if the driver doesn't know its step mode, for example
+    /// because it has never been set.
+    pub fn step_mode(&self) -> Option<Driver::StepMode>
+    where
+        Driver: SetStepMode + ConfiguredMicrosteps,
+    {
+        self.configured_microsteps()
+            .and_then(|mode| Driver::StepMode::try_from(mode).ok())
+    }
+
+    /// Check that the last motion has arrived at its target
+    ///
+    /// Compares the current step to the target of the last motion started via
+    /// [`MotionControl::move_to_position`]. A mismatch indicates that the
+    /// motion hasn't finished yet, or was aborted, for example by an error.
+    ///
+    /// This can't detect steps that the motor has lost, as there is no
+    /// feedback from the motor.
+    ///
+    /// Returns `Ok(())`, if no motion has been started since the position was
+    /// last reset.
+    pub fn assert_arrived(&self) -> Result<(), PositionMismatch> {
+        match self.target_step {
+            Some(expected) if expected != self.current_step => {
+                Err(PositionMismatch {
+                    expected,
+                    actual: self.current_step,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Take a snapshot of the current position and configuration
+    ///
+    /// See [`MotionState`] for details.
+    pub fn snapshot(&self) -> MotionState
+    where
+        Driver: ConfiguredMicrosteps,
+    {
+        MotionState {
+            current_step: self.current_step,
+            current_direction: self.current_direction,
+            step_mode: self.configured_microsteps(),
+        }
+    }
+
+    /// Restore a snapshot taken with [`Self::snapshot`]
+    ///
+    /// This never starts a motion. It only overwrites the internal position
+    /// and direction, and passes the step mode to the wrapped driver's
+    /// bookkeeping using [`SetStepMode::step_mode_set`]. Please note that the
+    /// step mode is not applied to the hardware. If the driver might have lost
+    /// its configuration, you need to set the step mode again after restoring.
+    /// A step mode the driver doesn't support is ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BusyError::Busy`], if a motion is ongoing.
+    pub fn restore(
+        &mut self,
+        state: MotionState,
+    ) -> Result<(), BusyError<Infallible>>
+    where
+        Driver: SetStepMode,
+    {
+        let driver = self.driver_mut().ok_or(BusyError::Busy)?;
+        if let Some(step_mode) = state
+            .step_mode
+            .and_then(|mode| Driver::StepMode::try_from(mode).ok())
+        {
+            driver.step_mode_set(step_mode);
+        }
+
+        self.current_step = state.current_step;
+        self.current_direction = state.current_direction;
+
+        Ok(())
+    }
+
+    /// Register a named position
+    ///
+    /// Stores `step` under `index`, replacing any position that was previously
+    /// registered there. Use [`Self::move_to_index`] to move to the position.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PositionError::InvalidIndex`], if `index` is not smaller than
+    /// [`MAX_POSITIONS`].
+    pub fn register_position(
+        &mut self,
+        index: usize,
+        step: i32,
+    ) -> Result<(), PositionError<Infallible>> {
+        let position = self
+            .positions
+            .get_mut(index)
+            .ok_or(PositionError::InvalidIndex)?;
+        *position = Some(step);
+
+        Ok(())
+    }
+
+    /// Access the position registered under `index`
+    ///
+    /// Returns `None`, if no position has been registered there.
+    pub fn position(&self, index: usize) -> Option<i32> {
+        self.positions.get(index).copied().flatten()
+    }
+
+    /// Limit the time between two steps
+    ///
+    /// Every delay between steps computed by the motion profile is clamped to
+    /// `min..=max`, before being passed to the timer. `min` protects the motor
+    /// from being driven too fast, `max` prevents very slow motions, which can
+    /// cause the motor to stall because of static friction.
+    ///
+    /// The bounds apply to the full step period, including the step pulse. If
+    /// `min` is larger than `max`, the two are swapped.
+    pub fn set_step_interval_bounds(
+        &mut self,
+        min: Nanoseconds,
+        max: Nanoseconds,
+    ) {
+        self.config.interval_bounds = Some(ordered(min, max));
+    }
+
+    /// Remove the limits set by [`Self::set_step_interval_bounds`]
+    pub fn clear_step_interval_bounds(&mut self) {
+        self.config.interval_bounds = None;
+    }
+
+    /// Define whether to wait for the setup time after setting direction
+    ///
+    /// By default, every motion that changes the direction waits for the
+    /// wrapped driver's setup time, before making the first step. If `skip` is
+    /// `true`, the first step is made right away. Only do this, if you can
+    /// guarantee that the DIR signal settles in time, for example because of
+    /// how the driver is wired.
+    pub fn set_skip_dir_setup_time(&mut self, skip: bool) {
+        self.config.skip_dir_setup_time = skip;
+    }
+
+    /// Set a function that is called whenever the internal state changes
+    ///
+    /// The function is called with the name of the new state, which is one of
+    /// `"Idle"`, `"SetDirection"`, `"Step"`, and `"StepDelay"`. This is meant
+    /// for debugging, for example by logging the state changes. Pass `None` to
+    /// remove the function again.
+    ///
+    /// The function is called from [`MotionControl::update`], so it should
+    /// return quickly, to not disturb the timing of the motion.
+    ///
+    /// This takes a function pointer, not a closure, so the callback can't
+    /// capture any state. Storing a closure would require either allocation,
+    /// which isn't available in `no_std` environments, or another type
+    /// parameter on `SoftwareMotionControl`. If the callback needs to access
+    /// state, keep that in a `static`, for example behind a critical section.
+    pub fn on_state_change(&mut self, callback: Option<fn(&'static str)>) {
+        self.config.on_state_change = callback;
+    }
+
+    /// Define whether to record how much later than commanded steps are made
+    ///
+    /// If `record` is `true`, the time between two steps is measured using the
+    /// timer's `now` method, and compared to the interval the motion profile
+    /// commanded. The largest difference is available through
+    /// [`Self::max_timing_error`]. This can help detect that
+    /// [`MotionControl::update`] isn't called often enough to keep up with the
+    /// requested velocity.
+    pub fn set_timing_recording(&mut self, record: bool) {
+        self.timing = if record { Some(Timing::new()) } else { None };
+    }
+
+    /// Access the largest timing error of the last motion
+    ///
+    /// This is the largest amount of time by which the interval between two
+    /// steps exceeded the commanded interval, since the last call to
+    /// [`MotionControl::move_to_position`]. Returns `None`, unless recording
+    /// has been enabled using [`Self::set_timing_recording`].
+    pub fn max_timing_error(&self) -> Option<TimerDuration<TIMER_HZ>> {
+        self.timing.as_ref().map(|timing| timing.max_error)
+    }
+
+    /// Set the highest velocity that motions may be started with
+    ///
+    /// Once a limit has been set, [`MotionControl::move_to_position`] rejects
+    /// any maximum velocity above it with [`Error::VelocityExceeded`], instead
+    /// of driving the motor faster than it can follow. The check applies to
+    /// the velocity after it has been raised out of the resonance band (see
+    /// [`Self::set_resonance_band`]), and to the approach creep velocity.
+    pub fn set_velocity_limit(&mut self, limit: Profile::Velocity) {
+        self.velocity_limit = Some(limit);
+    }
+
+    /// Remove the limit set by [`Self::set_velocity_limit`]
+    pub fn clear_velocity_limit(&mut self) {
+        self.velocity_limit = None;
+    }
+
+    /// Approach every target slowly, for precise settling
+    ///
+    /// Once set, [`MotionControl::move_to_position`] splits each motion into
+    /// two parts: A motion with the requested maximum velocity, that stops
+    /// `steps` short of the target, followed by a motion for the remaining
+    /// `steps` at `creep_velocity`. Motions that are not longer than `steps`
+    /// are made at `creep_velocity` entirely.
+    ///
+    /// This can help with loads that would otherwise overshoot the target.
+    pub fn set_approach_creep(
+        &mut self,
+        steps: u32,
+        creep_velocity: Profile::Velocity,
+    ) {
+        self.approach_creep = Some((steps, creep_velocity));
+    }
+
+    /// Remove the approach creep set by [`Self::set_approach_creep`]
+    pub fn clear_approach_creep(&mut self) {
+        self.approach_creep = None;
+    }
+
+    /// Define a velocity range the motor must not cruise in
+    ///
+    /// Some motors resonate at specific step rates, which can cause them to
+    /// stall. Once a band has been set, any maximum velocity passed to
+    /// [`MotionControl::move_to_position`] that lies within `low..high` is
+    /// raised to `high`. The motion profile will still accelerate and
+    /// decelerate through the band, but it will never plateau inside of it.
+    ///
+    /// The new band only applies to motions that are started after this
+    /// method has been called.
+    pub fn set_resonance_band(
+        &mut self,
+        low: Profile::Velocity,
+        high: Profile::Velocity,
+    ) {
+        self.resonance_band = Some((low, high));
+    }
+
+    /// Remove the velocity range set by [`Self::set_resonance_band`]
+    pub fn clear_resonance_band(&mut self) {
+        self.resonance_band = None;
+    }
+
+    /// Apply a whole configuration at once
+    ///
+    /// Replaces all settings that are part of [`MotionConfig`], including
+    /// removing those that are `None` in `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BusyError::Busy`], if a motion is ongoing. In that case,
+    /// none of the settings are changed.
+    pub fn configure(
+        &mut self,
+        config: MotionConfig<Profile::Velocity>,
+    ) -> Result<(), BusyError<Infallible>> {
+        if !matches!(self.state, State::Idle { .. }) {
+            return Err(BusyError::Busy);
+        }
+
+        self.config.interval_bounds =
+            config.step_interval_bounds.map(|(min, max)| {
+                ordered(
+                    Nanoseconds::from_ticks(min),
+                    Nanoseconds::from_ticks(max),
+                )
+            });
+        self.config.skip_dir_setup_time = config.skip_dir_setup_time;
+        self.velocity_limit = config.velocity_limit;
+        self.approach_creep = config.approach_creep;
+        self.resonance_band = config.resonance_band;
+
+        Ok(())
+    }
+
+    /// Access the current configuration
+    ///
+    /// See [`Self::configure`].
+    pub fn config(&self) -> MotionConfig<Profile::Velocity>
+    where
+        Profile::Velocity: Copy,
+    {
+        MotionConfig {
+            step_interval_bounds: self
+                .config
+                .interval_bounds
+                .map(|(min, max)| (min.ticks(), max.ticks())),
+            skip_dir_setup_time: self.config.skip_dir_setup_time,
+            velocity_limit: self.velocity_limit,
+            approach_creep: self.approach_creep,
+            resonance_band: self.resonance_band,
+        }
+    }
+
     /// Set step mode of the wrapped driver
     ///
     /// This method is a more convenient alternative to
@@ -171,7 +586,7 @@ where
         &mut self,
         step_mode: Driver::StepMode,
     ) -> Result<
-        SetStepModeFuture<RefMut<Driver>, RefMut<Timer>, TIMER_HZ>,
+        SetStepModeFuture<RefMut<'_, Driver>, RefMut<'_, Timer>, TIMER_HZ>,
         BusyError<Infallible>,
     >
     where
@@ -188,6 +603,101 @@ where
         Ok(future)
     }
 
+    /// Switch to the next finer step mode the driver supports
+    ///
+    /// Works like [`Self::set_step_mode`], using the step mode that follows
+    /// the current one in [`StepMode::iter`]. Once the returned future has
+    /// finished successfully, the current position is rescaled, so it refers
+    /// to the same physical position in the new step mode. The target and
+    /// start of the last motion are forgotten, like after
+    /// [`MotionControl::reset_position`].
+    ///
+    /// The current step mode is queried from the wrapped driver, see
+    /// [`ConfiguredMicrosteps`]. Returns `None`, if the current step mode is
+    /// already the finest one, or the driver doesn't know its step mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BusyError::Busy`], if a motion is ongoing.
+    pub fn microstep_up(
+        &mut self,
+    ) -> Result<
+        Option<SwitchStepModeFuture<'_, Driver, Timer, TIMER_HZ>>,
+        BusyError<Infallible>,
+    >
+    where
+        Driver: SetStepMode + ConfiguredMicrosteps,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        self.switch_step_mode(|current| {
+            Driver::StepMode::iter().find(|&mode| mode.into() > current)
+        })
+    }
+
+    /// Switch to the next coarser step mode the driver supports
+    ///
+    /// Like [`Self::microstep_up`], but in the other direction. When
+    /// rescaling, the position is rounded towards zero.
+    ///
+    /// Returns `None`, if the current step mode is already full steps, or the
+    /// driver doesn't know its step mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BusyError::Busy`], if a motion is ongoing.
+    pub fn microstep_down(
+        &mut self,
+    ) -> Result<
+        Option<SwitchStepModeFuture<'_, Driver, Timer, TIMER_HZ>>,
+        BusyError<Infallible>,
+    >
+    where
+        Driver: SetStepMode + ConfiguredMicrosteps,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        self.switch_step_mode(|current| {
+            Driver::StepMode::iter()
+                .take_while(|&mode| mode.into() < current)
+                .last()
+        })
+    }
+
+    fn switch_step_mode(
+        &mut self,
+        next: impl FnOnce(u16) -> Option<Driver::StepMode>,
+    ) -> Result<
+        Option<SwitchStepModeFuture<'_, Driver, Timer, TIMER_HZ>>,
+        BusyError<Infallible>,
+    >
+    where
+        Driver: SetStepMode + ConfiguredMicrosteps,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        let (driver, timer) = match &mut self.state {
+            State::Idle { driver, timer } => (driver, timer),
+            _ => return Err(BusyError::Busy),
+        };
+        let current = match driver.configured_microsteps() {
+            Some(current) => current,
+            None => return Ok(None),
+        };
+        let step_mode = match next(current) {
+            Some(step_mode) => step_mode,
+            None => return Ok(None),
+        };
+
+        self.target_step = None;
+        self.start_step = None;
+        self.approach = None;
+
+        Ok(Some(SwitchStepModeFuture::new(
+            SetStepModeFuture::new(step_mode, RefMut(driver), RefMut(timer)),
+            &mut self.current_step,
+            current,
+            step_mode.into(),
+        )))
+    }
+
     /// Set direction of the wrapped driver
     ///
     /// This method is a more convenient alternative to
@@ -207,7 +717,11 @@ where
         &mut self,
         direction: Direction,
     ) -> Result<
-        SetDirectionFuture<RefMut<Driver>, RefMut<Timer>, TIMER_HZ>,
+        SetDirectionFuture<
+            TrackDirection<'_, Driver>,
+            RefMut<'_, Timer>,
+            TIMER_HZ,
+        >,
         BusyError<Infallible>,
     >
     where
@@ -217,7 +731,10 @@ where
         let future = match &mut self.state {
             State::Idle { driver, timer } => SetDirectionFuture::new(
                 direction,
-                RefMut(driver),
+                TrackDirection {
+                    driver,
+                    current_direction: &mut self.current_direction,
+                },
                 RefMut(timer),
             ),
             _ => return Err(BusyError::Busy),
@@ -236,6 +753,10 @@ where
     /// is only available, if you statically know that you're working with a
     /// driver wrapped by `SoftwareMotionControl`.
     ///
+    /// Unlike [`Stepper::step`], this method can't update the current step, as
+    /// the returned future only has access to the wrapped driver. Use
+    /// [`Self::step_counted`], if you need the position to stay accurate.
+    ///
     /// # Errors
     ///
     /// Returns [`BusyError::Busy`], if a motion is ongoing.
@@ -244,7 +765,7 @@ where
     pub fn step(
         &mut self,
     ) -> Result<
-        StepFuture<RefMut<Driver>, RefMut<Timer>, TIMER_HZ>,
+        StepFuture<RefMut<'_, Driver>, RefMut<'_, Timer>, TIMER_HZ>,
         BusyError<Infallible>,
     >
     where
@@ -260,61 +781,830 @@ where
 
         Ok(future)
     }
-}
-
-impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> MotionControl
-    for SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
-where
-    Driver: SetDirection + Step,
-    Profile: MotionProfile,
-    Timer: TimerTrait<TIMER_HZ>,
-    Profile::Velocity: Copy,
-    Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
-{
-    type Velocity = Profile::Velocity;
-    type Error = Error<
-        <Driver as SetDirection>::Error,
-        <<Driver as SetDirection>::Dir as ErrorType>::Error,
-        <Driver as Step>::Error,
-        <<Driver as Step>::Step as ErrorType>::Error,
-        Timer::Error,
-        Convert::Error,
-    >;
 
-    fn move_to_position(
+    /// Move the motor one step and return the new position
+    ///
+    /// Makes one step in the current direction (see
+    /// [`Self::current_direction`]) and updates the current step accordingly.
+    /// This is useful, if you need to update a display after every step, for
+    /// example.
+    ///
+    /// Unlike most other methods, this one blocks until the operation has
+    /// finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BusyError::Busy`], if a motion is ongoing.
+    pub fn step_counted(
         &mut self,
-        max_velocity: Self::Velocity,
-        target_step: i32,
-    ) -> Result<(), Self::Error> {
-        let steps_from_here = target_step - self.current_step;
+    ) -> Result<
+        i32,
+        BusyError<
+            SignalError<
+                Driver::Error,
+                <Driver::Step as ErrorType>::Error,
+                Timer::Error,
+            >,
+        >,
+    >
+    where
+        Driver: Step,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        let (driver, timer) = match &mut self.state {
+            State::Idle { driver, timer } => (driver, timer),
+            _ => return Err(BusyError::Busy),
+        };
 
-        self.profile
-            .enter_position_mode(max_velocity, steps_from_here.abs() as u32);
+        StepFuture::new(RefMut(driver), RefMut(timer))
+            .wait()
+            .map_err(BusyError::Other)?;
+        self.current_step = self
+            .current_step
+            .wrapping_add(self.current_direction as i32);
 
-        let direction = if steps_from_here > 0 {
-            Direction::Forward
-        } else {
-            Direction::Backward
+        Ok(self.current_step)
+    }
+
+    /// Move the motor one step against the current direction
+    ///
+    /// Sets the direction to the opposite of [`Self::current_direction`], makes
+    /// one step, then restores the previous direction. The current step is
+    /// updated accordingly. Both direction changes respect the setup time of
+    /// the wrapped driver.
+    ///
+    /// Unlike most other methods, this one blocks until the operation has
+    /// finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BusyError::Busy`], if a motion is ongoing.
+    pub fn step_reverse(
+        &mut self,
+    ) -> Result<
+        (),
+        BusyError<
+            Error<
+                <Driver as SetDirection>::Error,
+                <<Driver as SetDirection>::Dir as ErrorType>::Error,
+                <Driver as Step>::Error,
+                <<Driver as Step>::Step as ErrorType>::Error,
+                Timer::Error,
+                Infallible,
+            >,
+        >,
+    >
+    where
+        Driver: SetDirection + Step,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        let (driver, timer) = match &mut self.state {
+            State::Idle { driver, timer } => (driver, timer),
+            _ => return Err(BusyError::Busy),
         };
-        self.new_motion = Some(direction);
 
-        Ok(())
-    }
+        let prior = self.current_direction;
+        let reverse = match prior {
+            Direction::Forward => Direction::Backward,
+            Direction::Backward => Direction::Forward,
+        };
+
+        SetDirectionFuture::new(reverse, RefMut(driver), RefMut(timer))
+            .wait()
+            .map_err(|err| BusyError::Other(Error::SetDirection(err)))?;
+        StepFuture::new(RefMut(driver), RefMut(timer))
+            .wait()
+            .map_err(|err| BusyError::Other(Error::Step(err)))?;
+        self.current_step = self.current_step.wrapping_add(reverse as i32);
+        SetDirectionFuture::new(prior, RefMut(driver), RefMut(timer))
+            .wait()
+            .map_err(|err| BusyError::Other(Error::SetDirection(err)))?;
 
-    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
-        self.current_step = step;
         Ok(())
     }
+}
 
-    fn update(&mut self) -> Result<bool, Self::Error> {
-        // Otherwise the closure will borrow all of `self`.
-        let new_motion = &mut self.new_motion;
-        let profile = &mut self.profile;
-        let current_step = &mut self.current_step;
-        let current_direction = &mut self.current_direction;
-        let convert = &self.convert;
-
-        replace_with_and_return(
+impl<Driver, Timer, Num, Convert, const TIMER_HZ: u32>
+    SoftwareMotionControl<Driver, Timer, Trapezoidal<Num>, Convert, TIMER_HZ>
+where
+    Trapezoidal<Num>: MotionProfile,
+    Num: Copy
+        + num_traits::One
+        + num_traits::FromPrimitive
+        + ops::Add<Output = Num>
+        + ops::Div<Output = Num>
+        + Sqrt,
+{
+    /// Configure the acceleration by the time it takes to reach full speed
+    ///
+    /// Replaces the motion profile with a new [`Trapezoidal`] profile, whose
+    /// acceleration is `max_velocity / accel_time`. This means motions with
+    /// the given maximum velocity reach that velocity after `accel_time`, and
+    /// take the same time to decelerate again.
+    ///
+    /// `max_velocity` must be defined in steps per timer tick, like in the
+    /// example in the [crate-level documentation](crate).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BusyError::Busy`], if a motion is ongoing, or
+    /// [`BusyError::Other`], if `accel_time` is zero or can't be represented
+    /// as `Num`.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `max_velocity` is zero.
+    pub fn set_accel_time(
+        &mut self,
+        max_velocity: Num,
+        accel_time: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), BusyError<InvalidAccelTime>> {
+        if !matches!(self.state, State::Idle { .. }) {
+            return Err(BusyError::Busy);
+        }
+
+        if accel_time.ticks() == 0 {
+            return Err(BusyError::Other(InvalidAccelTime));
+        }
+        let accel_time = Num::from_u32(accel_time.ticks())
+            .ok_or(BusyError::Other(InvalidAccelTime))?;
+
+        self.profile = Trapezoidal::new(max_velocity / accel_time);
+
+        Ok(())
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>
+    SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Self: MotionControl<Velocity = Profile::Velocity>,
+    Profile: MotionProfile,
+    Profile::Velocity: Copy,
+{
+    /// Move to a position registered with [`Self::register_position`]
+    ///
+    /// Works like [`MotionControl::move_to_position`], and like that method,
+    /// only starts the motion. Call [`MotionControl::update`] until it returns
+    /// `false` to finish it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PositionError::InvalidIndex`], if no position has been
+    /// registered under `index`.
+    pub fn move_to_index(
+        &mut self,
+        max_velocity: Profile::Velocity,
+        index: usize,
+    ) -> Result<(), PositionError<<Self as MotionControl>::Error>> {
+        let target_step =
+            self.position(index).ok_or(PositionError::InvalidIndex)?;
+        self.move_to_position(max_velocity, target_step)
+            .map_err(PositionError::Other)
+    }
+
+    /// Stop the ongoing motion
+    ///
+    /// The motor decelerates to a stop according to the motion profile, in
+    /// the direction it's currently moving in. Call [`MotionControl::update`]
+    /// until it returns `false` to finish the motion. Does nothing, if no
+    /// motion is ongoing.
+    ///
+    /// Works with any motion, but is most useful to end one started by
+    /// [`Self::move_at_velocity`].
+    pub fn stop(&mut self) {
+        if let Some(velocity) = self.velocity {
+            self.profile.enter_position_mode(velocity, 0);
+        }
+
+        self.new_motion = None;
+        self.approach = None;
+        self.target_step = None;
+        self.continuous = None;
+    }
+
+    /// Set the direction for a move to `target_step`, ahead of time
+    ///
+    /// Sets the direction implied by the target, and starts waiting for the
+    /// setup time. Call [`MotionControl::update`] until it returns `false` to
+    /// finish this, then start the move with [`Self::execute_prepared`]. Unlike
+    /// [`Self::arm_move`], this doesn't block, so the setup time can overlap
+    /// with other work.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BusyError::Busy`], if a motion is ongoing.
+    pub fn prepare_move(
+        &mut self,
+        target_step: i32,
+    ) -> Result<(), BusyError<Infallible>> {
+        if !matches!(self.state, State::Idle { .. })
+            || self.new_motion.is_some()
+        {
+            return Err(BusyError::Busy);
+        }
+
+        let direction = if target_step > self.current_step {
+            Direction::Forward
+        } else {
+            Direction::Backward
+        };
+        self.new_motion = Some(direction);
+        self.prepared = Some((target_step, direction));
+
+        Ok(())
+    }
+
+    /// Start the move prepared with [`Self::prepare_move`]
+    ///
+    /// Works like [`MotionControl::move_to_position`], but doesn't set the
+    /// direction again, so the first step can be made right away. If the
+    /// setup time hasn't passed yet, the first step is made once it has.
+    ///
+    /// Does nothing, if no move has been prepared, or if the prepared move
+    /// has been overridden by another motion since.
+    pub fn execute_prepared(
+        &mut self,
+        max_velocity: Profile::Velocity,
+    ) -> Result<(), <Self as MotionControl>::Error> {
+        let (target_step, direction) = match self.prepared {
+            Some(prepared) => prepared,
+            None => return Ok(()),
+        };
+
+        // `update` takes the direction from `new_motion` when it starts setting
+        // it. If it hasn't done so yet, the direction still needs to be set.
+        let direction_set =
+            self.new_motion.is_none() && self.current_direction == direction;
+
+        self.move_to_position(max_velocity, target_step)?;
+
+        // The direction has already been set (or is still being set), so
+        // there's no need to do it again.
+        if direction_set {
+            self.new_motion = None;
+            if let Some(timing) = &mut self.timing {
+                timing.motion_started();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prepare a motion, without starting to step yet
+    ///
+    /// Plans the motion like [`MotionControl::move_to_position`] would, and
+    /// sets the direction, including waiting for the setup time. The motor
+    /// doesn't move, until [`Self::trigger`] is called. This keeps the latency
+    /// between the trigger and the first step low, which is useful for
+    /// starting multiple axes at the same time.
+    ///
+    /// While the motion is armed, [`MotionControl::update`] returns `true`, but
+    /// doesn't make any steps.
+    ///
+    /// Unlike most other methods, this one blocks until the direction has
+    /// been set.
+    pub fn arm_move(
+        &mut self,
+        max_velocity: Profile::Velocity,
+        target_step: i32,
+    ) -> Result<(), <Self as MotionControl>::Error> {
+        self.move_to_position(max_velocity, target_step)?;
+        self.config.armed = true;
+
+        while self.new_motion.is_some()
+            || !matches!(self.state, State::Idle { .. })
+        {
+            if let Err(err) = self.update() {
+                self.config.armed = false;
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Start a motion prepared with [`Self::arm_move`]
+    ///
+    /// The first step is made on the next call to [`MotionControl::update`].
+    /// Does nothing, if no motion is armed.
+    pub fn trigger(&mut self) {
+        self.config.armed = false;
+    }
+
+    /// Move back to where the last motion started
+    ///
+    /// Moves to the position the motor was at, when the last motion was
+    /// started via [`MotionControl::move_to_position`]. The motion itself is
+    /// not remembered as a start position, so calling this method repeatedly
+    /// always returns to the same position.
+    ///
+    /// Like [`MotionControl::move_to_position`], this only starts the motion.
+    /// Does nothing, if no motion has been started since the position was last
+    /// reset.
+    pub fn return_to_start(
+        &mut self,
+        max_velocity: Profile::Velocity,
+    ) -> Result<(), <Self as MotionControl>::Error> {
+        if let Some(start_step) = self.start_step {
+            self.move_to_position(max_velocity, start_step)?;
+            self.start_step = Some(start_step);
+        }
+
+        Ok(())
+    }
+
+    /// Measure the travel range by driving into both mechanical end stops
+    ///
+    /// Moves backward one step at a time, until `stall` returns `true`, then
+    /// defines that position as step `0`. Then moves forward the same way,
+    /// until `stall` returns `true` again, and returns the number of steps
+    /// between both end stops. Afterwards, the motor is left at the forward
+    /// end stop.
+    ///
+    /// `stall` is called after each step. It should detect whether the motor
+    /// has run into an end stop, for example by monitoring the motor current.
+    /// Keep `max_velocity` low, to drive into the end stops gently.
+    ///
+    /// Unlike most other methods, this one blocks until the operation has
+    /// finished. It never returns, if `stall` never returns `true`.
+    pub fn measure_travel(
+        &mut self,
+        max_velocity: Profile::Velocity,
+        mut stall: impl FnMut() -> bool,
+    ) -> Result<i32, <Self as MotionControl>::Error> {
+        for &direction in &[Direction::Backward, Direction::Forward] {
+            loop {
+                let target_step = self.current_step + direction as i32;
+                self.move_to_position(max_velocity, target_step)?;
+                while self.update()? {}
+
+                if stall() {
+                    break;
+                }
+            }
+
+            if direction == Direction::Backward {
+                self.reset_position(0)?;
+            }
+        }
+
+        Ok(self.current_step)
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> MotionControl
+    for SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Driver: SetDirection + Step,
+    Profile: MotionProfile,
+    Timer: TimerTrait<TIMER_HZ>,
+    Profile::Velocity: Copy + PartialOrd,
+    Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
+{
+    type Velocity = Profile::Velocity;
+    type Error = Error<
+        <Driver as SetDirection>::Error,
+        <<Driver as SetDirection>::Dir as ErrorType>::Error,
+        <Driver as Step>::Error,
+        <<Driver as Step>::Step as ErrorType>::Error,
+        Timer::Error,
+        Convert::Error,
+    >;
+
+    fn move_to_position(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        let mut max_velocity = self
+            .limit_velocity(max_velocity)
+            .ok_or(Error::VelocityExceeded)?;
+
+        let current_step = self.committed_step();
+
+        let distance =
+            (i64::from(target_step) - i64::from(current_step)).unsigned_abs();
+        if distance > u64::from(MAX_MOVE_STEPS) {
+            return Err(Error::MoveTooLong);
+        }
+
+        // If an approach creep is configured, stop short of the target, then
+        // make the rest of the motion at the creep velocity.
+        let mut first_target = target_step;
+        let mut approach = None;
+        if let Some((creep_steps, creep_velocity)) = self.approach_creep {
+            let creep_velocity = self
+                .limit_velocity(creep_velocity)
+                .ok_or(Error::VelocityExceeded)?;
+
+            let steps_from_here = target_step - current_step;
+            if steps_from_here.unsigned_abs() > creep_steps {
+                let creep_steps = i32::try_from(creep_steps)
+                    .map_err(|_| Error::MoveTooLong)?;
+                first_target =
+                    target_step - steps_from_here.signum() * creep_steps;
+                approach = Some((creep_velocity, target_step));
+            } else {
+                max_velocity = creep_velocity;
+            }
+        }
+
+        self.approach = approach;
+        self.start_motion(max_velocity, first_target);
+        self.target_step = Some(target_step);
+        self.start_step = Some(current_step);
+        self.velocity = Some(max_velocity);
+        self.continuous = None;
+        self.prepared = None;
+        if let Some(timing) = &mut self.timing {
+            timing.max_error = TimerDuration::from_ticks(0);
+        }
+
+        Ok(())
+    }
+
+    fn move_by(
+        &mut self,
+        max_velocity: Self::Velocity,
+        steps: i32,
+    ) -> Result<(), Self::Error> {
+        let target_step = self
+            .committed_step()
+            .checked_add(steps)
+            .ok_or(Error::MoveTooLong)?;
+        self.move_to_position(max_velocity, target_step)
+    }
+
+    /// Change the target of an ongoing motion, without stopping first
+    ///
+    /// If the new target is ahead, in the direction of the ongoing motion, this
+    /// works like [`MotionControl::move_to_position`], except that the
+    /// direction isn't set again. The motion profile continues from its
+    /// current velocity, without waiting for the DIR setup time.
+    ///
+    /// If the new target is behind, the motor decelerates to a stop according
+    /// to the motion profile, then moves back to the new target. The same
+    /// happens, if the motor can't stop in time to reach a target ahead.
+    ///
+    /// Whether a motion can continue from its current velocity depends on the
+    /// motion profile. [`Trapezoidal`] supports this.
+    ///
+    /// Like [`MotionControl::move_to_position`], this only starts the motion.
+    fn retarget(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        let moving = !matches!(self.state, State::Idle { .. });
+        self.move_to_position(max_velocity, target_step)?;
+
+        let steps_from_here = target_step - self.committed_step();
+        let is_behind = match self.current_direction {
+            Direction::Forward => steps_from_here < 0,
+            Direction::Backward => steps_from_here > 0,
+        };
+
+        if is_behind {
+            // Keep going in the current direction, letting the profile ramp
+            // down from whatever velocity it's at. That's a no-op, if the motor
+            // is at rest.
+            self.profile.enter_position_mode(max_velocity, 0);
+            self.new_motion = None;
+        } else if moving && self.new_motion == Some(self.current_direction) {
+            // The motor is already moving in the right direction.
+            self.new_motion = None;
+        }
+
+        // The profile might not be able to stop at the target. If it
+        // overshoots, come back.
+        if self.approach.is_none() || is_behind {
+            self.approach = Some((max_velocity, target_step));
+        }
+
+        Ok(())
+    }
+
+    fn current_position(&self) -> Result<i32, Self::Error> {
+        Ok(self.current_step)
+    }
+
+    fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
+        self.current_step = step;
+        self.target_step = None;
+        self.start_step = None;
+        self.approach = None;
+        self.prepared = None;
+        Ok(())
+    }
+
+    fn update(&mut self) -> Result<bool, Self::Error> {
+        let step_before = self.current_step;
+        let still_moving = self.update_state()?;
+
+        // A continuous rotation must never reach its end, so extend it again
+        // after every step.
+        if let Some(velocity) = self.continuous {
+            if self.current_step != step_before {
+                self.profile.enter_position_mode(velocity, MAX_MOVE_STEPS);
+            }
+        }
+
+        if !still_moving {
+            if let Some((velocity, target_step)) = self.approach.take() {
+                if target_step != self.current_step {
+                    self.start_motion(velocity, target_step);
+                    return self.update_state();
+                }
+            }
+        }
+
+        Ok(still_moving)
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>
+    SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Driver: SetDirection + Step,
+    Profile: MotionProfile,
+    Timer: TimerTrait<TIMER_HZ>,
+    Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
+{
+    /// Rotate continuously in the given direction, until [`Self::stop`] is
+    /// called
+    ///
+    /// The motion profiles provided by RampMaker don't have a velocity mode.
+    /// Instead, this starts a motion over the largest number of steps a single
+    /// motion can make, and [`MotionControl::update`] extends it by that number
+    /// again after every step. This way, the motion never comes close to its
+    /// end, and the profile never starts to ramp down on its own.
+    ///
+    /// Other than that, this works like [`MotionControl::move_to_position`],
+    /// including the velocity limit and resonance band, and like that method,
+    /// only starts the motion. Starting any other motion ends the continuous
+    /// rotation.
+    ///
+    /// The direction is passed separately, as velocities are not necessarily
+    /// signed. The current step wraps around, once it reaches the end of the
+    /// `i32` range.
+    ///
+    /// This is a method of `SoftwareMotionControl`, not part of
+    /// [`MotionControl`]. Adding it to the trait would require every
+    /// implementation to support continuous rotation, and a default
+    /// implementation can't provide it on top of
+    /// [`MotionControl::move_to_position`]. Here, it relies on extending the
+    /// motion from [`MotionControl::update`], which only the software fallback
+    /// can do.
+    pub fn move_at_velocity(
+        &mut self,
+        velocity: Profile::Velocity,
+        direction: Direction,
+    ) -> Result<(), <Self as MotionControl>::Error>
+    where
+        Profile::Velocity: Copy + PartialOrd,
+    {
+        let velocity = self
+            .limit_velocity(velocity)
+            .ok_or(Error::VelocityExceeded)?;
+
+        self.profile.enter_position_mode(velocity, MAX_MOVE_STEPS);
+        self.new_motion = Some(direction);
+
+        // There is no target to arrive at.
+        self.approach = None;
+        self.target_step = None;
+        self.start_step = Some(self.committed_step());
+        self.velocity = Some(velocity);
+        self.continuous = Some(velocity);
+        self.prepared = None;
+        if let Some(timing) = &mut self.timing {
+            timing.max_error = TimerDuration::from_ticks(0);
+        }
+
+        Ok(())
+    }
+
+    /// Oscillate around a center position
+    ///
+    /// Swings out to `center + amplitude`, then alternates between
+    /// `center - amplitude` and `center + amplitude`, reversing the direction
+    /// once per cycle, then comes to rest at `center`. Each cycle is worth
+    /// `2 * amplitude` steps, so if the motor starts at `center`, this makes
+    /// `2 * amplitude * cycles` steps in total. For example, 3 cycles go from
+    /// `center` to `+amplitude`, `-amplitude`, `+amplitude`, and back to
+    /// `center`.
+    ///
+    /// Each swing is a full motion according to the motion profile, so the
+    /// motor comes to a stop at every reversal.
+    ///
+    /// There is no backlash compensation. Every reversal is made with exactly
+    /// the commanded number of steps, so any play in the mechanics reduces the
+    /// distance the load actually travels.
+    ///
+    /// Unlike most other methods, this one blocks until the operation has
+    /// finished.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MoveTooLong`] without moving, if `center + amplitude`
+    /// or `center - amplitude` can't be represented.
+    pub fn oscillate(
+        &mut self,
+        max_velocity: Profile::Velocity,
+        center: i32,
+        amplitude: u32,
+        cycles: u32,
+    ) -> Result<(), <Self as MotionControl>::Error>
+    where
+        Profile::Velocity: Copy + PartialOrd,
+    {
+        let amplitude =
+            i32::try_from(amplitude).map_err(|_| Error::MoveTooLong)?;
+        let ahead = center.checked_add(amplitude).ok_or(Error::MoveTooLong)?;
+        let behind = center.checked_sub(amplitude).ok_or(Error::MoveTooLong)?;
+
+        let swings = (0..cycles)
+            .map(|cycle| if cycle % 2 == 0 { ahead } else { behind })
+            .chain([center]);
+
+        for target_step in swings {
+            self.move_to_position(max_velocity, target_step)?;
+            while self.update()? {}
+        }
+
+        Ok(())
+    }
+
+    /// Export the schedule of a motion as CSV, for analysis on a host
+    ///
+    /// Plans a motion of `num_steps` steps at `max_velocity`, and returns one
+    /// row per step, with the columns `step_index`, `cumulative_time_ns`,
+    /// `interval_ns` and `velocity` (in steps per second). The first line is a
+    /// header. The intervals are the full step periods [`MotionControl::update`]
+    /// would wait for, including the limits set by
+    /// [`Self::set_step_interval_bounds`]. If an interval is zero, the velocity
+    /// is left empty.
+    ///
+    /// This doesn't move the motor, but it plans the motion using the wrapped
+    /// motion profile, and runs the profile to its end. Whatever state the
+    /// profile was in before is lost. Afterwards, the profile is at rest, as it
+    /// would be after the motion had been made.
+    ///
+    /// Only available, if the `std` feature is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BusyError::Busy`], if a motion is ongoing, or
+    /// [`BusyError::Other`], if a delay couldn't be converted into timer ticks.
+    #[cfg(feature = "std")]
+    pub fn export_schedule_csv(
+        &mut self,
+        max_velocity: Profile::Velocity,
+        num_steps: u32,
+    ) -> Result<
+        std::string::String,
+        BusyError<TimeConversionError<Convert::Error>>,
+    > {
+        use std::fmt::Write as _;
+
+        if !matches!(self.state, State::Idle { .. })
+            || self.new_motion.is_some()
+        {
+            return Err(BusyError::Busy);
+        }
+
+        let mut csv = std::string::String::from(
+            "step_index,cumulative_time_ns,interval_ns,velocity\n",
+        );
+        let mut cumulative_time_ns = 0;
+
+        self.profile.enter_position_mode(max_velocity, num_steps);
+        let mut step_index = 0;
+        while let Some(delay) = self.profile.next_delay() {
+            let ticks: TimerDuration<TIMER_HZ> = state::step_interval(
+                delay,
+                self.config.interval_bounds,
+                &self.convert,
+            )
+            .map_err(BusyError::Other)?;
+            let interval_ns =
+                u64::from(ticks.ticks()) * 1_000_000_000 / u64::from(TIMER_HZ);
+            cumulative_time_ns += interval_ns;
+
+            // Writing to a `String` can't fail.
+            let _ = write!(
+                csv,
+                "{},{},{},",
+                step_index, cumulative_time_ns, interval_ns
+            );
+            if interval_ns > 0 {
+                let _ = write!(csv, "{}", 1_000_000_000.0 / interval_ns as f64);
+            }
+            csv.push('\n');
+            step_index += 1;
+        }
+
+        Ok(csv)
+    }
+
+    /// Stop the ongoing motion immediately, without decelerating
+    ///
+    /// Unlike [`Self::stop`], this doesn't ramp down according to the motion
+    /// profile. No more steps are made, and a STEP pulse that is in progress is
+    /// cut short. Stopping abruptly from a high velocity can make the motor
+    /// lose steps, especially under load.
+    ///
+    /// The current step stays at the last step that was completed. A pulse
+    /// that is cut short isn't counted, even though most drivers step on its
+    /// rising edge, so the position can be one step off afterwards. Does
+    /// nothing, if no motion is ongoing.
+    ///
+    /// The motor coils stay energized, so the motor holds its position. None
+    /// of the driver traits control the EN pin, so there is no option to
+    /// release the coils. If that is required, disable the driver through its
+    /// EN pin after calling this method.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Step`], if a STEP pulse was in progress and couldn't
+    /// be ended. The motion is stopped regardless.
+    pub fn emergency_stop(
+        &mut self,
+    ) -> Result<
+        (),
+        Error<
+            <Driver as SetDirection>::Error,
+            <<Driver as SetDirection>::Dir as ErrorType>::Error,
+            <Driver as Step>::Error,
+            <<Driver as Step>::Step as ErrorType>::Error,
+            Timer::Error,
+            Convert::Error,
+        >,
+    > {
+        self.new_motion = None;
+        self.approach = None;
+        self.target_step = None;
+        self.continuous = None;
+        self.prepared = None;
+        self.config.armed = false;
+
+        // Make sure the profile has no steps left. Entering position mode with
+        // zero steps leaves only the ramp down, which is discarded here.
+        if let Some(velocity) = self.velocity.take() {
+            self.profile.enter_position_mode(velocity, 0);
+            while self.profile.next_delay().is_some() {}
+        }
+
+        let mut result = Ok(());
+        let (driver, timer) =
+            match mem::replace(&mut self.state, State::Invalid) {
+                State::Idle { driver, timer }
+                | State::StepDelay { driver, timer } => (driver, timer),
+                State::SetDirection(future) => future.release(),
+                State::Step { future, .. } => {
+                    let (mut driver, timer) = future.release();
+                    result = driver
+                        .step()
+                        .map_err(SignalError::PinUnavailable)
+                        .and_then(|step| {
+                            step.set_low().map_err(SignalError::Pin)
+                        })
+                        .map_err(Error::Step);
+                    (driver, timer)
+                }
+                State::Invalid => return Ok(()),
+            };
+        self.state = State::Idle { driver, timer };
+        self.config.notify("Idle");
+
+        result
+    }
+
+    /// Advance the internal state, without handling the approach creep
+    fn update_state(
+        &mut self,
+    ) -> Result<
+        bool,
+        Error<
+            <Driver as SetDirection>::Error,
+            <<Driver as SetDirection>::Dir as ErrorType>::Error,
+            <Driver as Step>::Error,
+            <<Driver as Step>::Step as ErrorType>::Error,
+            Timer::Error,
+            Convert::Error,
+        >,
+    > {
+        // Otherwise the closure will borrow all of `self`.
+        let new_motion = &mut self.new_motion;
+        let profile = &mut self.profile;
+        let current_step = &mut self.current_step;
+        let current_direction = &mut self.current_direction;
+        let convert = &self.convert;
+        let config = self.config;
+        let timing = &mut self.timing;
+
+        replace_with_and_return(
             &mut self.state,
             || State::Invalid,
             |state| {
@@ -325,12 +1615,23 @@ where
                     current_step,
                     current_direction,
                     convert,
+                    config,
+                    timing,
                 )
             },
         )
     }
 }
 
+/// Swap `min` and `max`, if they have been passed in the wrong order
+fn ordered(min: Nanoseconds, max: Nanoseconds) -> (Nanoseconds, Nanoseconds) {
+    if min <= max {
+        (min, max)
+    } else {
+        (max, min)
+    }
+}
+
 // We could also implement the various "enable" traits here, but those
 // implementations can only work while we have access to the driver, which
 // mostly means we'd have to be idle. Since the "enable" traits are infallible,
@@ -355,19 +1656,34 @@ where
         match self.driver_mut() {
             Some(driver) => driver
                 .apply_mode_config(step_mode)
-                .map_err(|err| BusyError::Other(err)),
+                .map_err(BusyError::Other),
             None => Err(BusyError::Busy),
         }
     }
 
     fn enable_driver(&mut self) -> Result<(), Self::Error> {
         match self.driver_mut() {
-            Some(driver) => {
-                driver.enable_driver().map_err(|err| BusyError::Other(err))
-            }
+            Some(driver) => driver.enable_driver().map_err(BusyError::Other),
             None => Err(BusyError::Busy),
         }
     }
+
+    fn step_mode_set(&mut self, step_mode: Self::StepMode) {
+        if let Some(driver) = self.driver_mut() {
+            driver.step_mode_set(step_mode);
+        }
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> ConfiguredMicrosteps
+    for SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>
+where
+    Driver: ConfiguredMicrosteps,
+    Profile: MotionProfile,
+{
+    fn configured_microsteps(&self) -> Option<u16> {
+        self.state.driver()?.configured_microsteps()
+    }
 }
 
 impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> SetDirection
@@ -383,10 +1699,17 @@ where
 
     fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
         match self.driver_mut() {
-            Some(driver) => driver.dir().map_err(|err| BusyError::Other(err)),
+            Some(driver) => driver.dir().map_err(BusyError::Other),
             None => Err(BusyError::Busy),
         }
     }
+
+    fn direction_set(&mut self, direction: Direction) {
+        self.current_direction = direction;
+        if let Some(driver) = self.driver_mut() {
+            driver.direction_set(direction);
+        }
+    }
 }
 
 impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32> Step
@@ -402,10 +1725,19 @@ where
 
     fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
         match self.driver_mut() {
-            Some(driver) => driver.step().map_err(|err| BusyError::Other(err)),
+            Some(driver) => driver.step().map_err(BusyError::Other),
             None => Err(BusyError::Busy),
         }
     }
+
+    fn step_made(&mut self) {
+        self.current_step = self
+            .current_step
+            .wrapping_add(self.current_direction as i32);
+        if let Some(driver) = self.driver_mut() {
+            driver.step_made();
+        }
+    }
 }
 
 // Blanket implementation of `EnableMotionControl` for all STEP/DIR stepper
@@ -416,7 +1748,7 @@ where
     Driver: SetDirection + Step,
     Profile: MotionProfile,
     Timer: TimerTrait<TIMER_HZ>,
-    Profile::Velocity: Copy,
+    Profile::Velocity: Copy + PartialOrd,
     Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
 {
     type WithMotionControl =
@@ -429,3 +1761,1031 @@ where
         SoftwareMotionControl::new(self, timer, profile, convert)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use fugit::{
+        NanosDurationU32 as Nanoseconds, TimerDurationU32 as TimerDuration,
+    };
+    use ramp_maker::{Flat, MotionProfile as _, Trapezoidal};
+
+    use crate::{
+        motor::MotorSpec,
+        step_mode::StepMode256,
+        test_utils::{
+            ClockTimer, FixedTicks, MockTimer, MotionFixture, PinError,
+            RecordingDriver, ScriptedProfile, Ticks, TIMER_HZ,
+        },
+        Direction,
+    };
+
+    use super::{
+        BusyError, Error, InvalidAccelTime, MotionConfig, MotionControl as _,
+        PositionError, PositionMismatch, SimpleError, SoftwareMotionControl,
+        MAX_MOVE_STEPS, MAX_POSITIONS,
+    };
+
+    #[test]
+    fn move_to_position_should_generate_steps_in_the_right_direction() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+
+        stepper.move_to_position(0.001, 3).wait().unwrap();
+        stepper.move_to_position(0.001, 1).wait().unwrap();
+
+        use Direction::*;
+        assert_eq!(
+            recording.borrow().steps,
+            [
+                (Forward, 0),
+                (Forward, 1),
+                (Forward, 2),
+                (Backward, 3),
+                (Backward, 4)
+            ]
+        );
+        assert_eq!(stepper.driver().current_step(), 1);
+    }
+
+    #[test]
+    fn move_to_position_should_set_direction_once_per_move() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+
+        stepper.move_to_position(0.001, 5).wait().unwrap();
+        assert_eq!(recording.borrow().dir_accesses, 1);
+
+        stepper.move_to_position(0.001, 2).wait().unwrap();
+        assert_eq!(recording.borrow().dir_accesses, 2);
+        assert_eq!(recording.borrow().steps.len(), 8);
+    }
+
+    #[test]
+    fn move_to_position_should_not_wait_if_dir_setup_time_is_skipped() {
+        let MotionFixture {
+            mut stepper,
+            durations,
+            ..
+        } = MotionFixture::new();
+        stepper.driver_mut().set_skip_dir_setup_time(true);
+
+        stepper.move_to_position(0.003_906_25, 1).wait().unwrap();
+
+        // Only the pulse and the rest of the step delay, no direction setup.
+        let ticks: Vec<_> =
+            durations.borrow().iter().map(|d| d.ticks()).collect();
+        assert_eq!(ticks, [1, 255]);
+    }
+
+    #[test]
+    fn move_to_position_should_reject_velocity_above_limit() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+        stepper.driver_mut().set_velocity_limit(0.001);
+
+        let result = stepper.move_to_position(0.002, 3).wait();
+
+        assert_eq!(result, Err(Error::VelocityExceeded));
+        assert_eq!(recording.borrow().dir_accesses, 0);
+        assert!(recording.borrow().steps.is_empty());
+
+        stepper.move_to_position(0.001, 3).wait().unwrap();
+        assert_eq!(stepper.driver().current_step(), 3);
+    }
+
+    #[test]
+    fn velocity_limit_should_apply_after_resonance_band_and_to_creep() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+        let smc = stepper.driver_mut();
+        smc.set_velocity_limit(0.002);
+
+        // Below the limit, but the band raises it above.
+        smc.set_resonance_band(0.001, 0.003);
+        assert_eq!(
+            smc.move_to_position(0.0015, 3),
+            Err(Error::VelocityExceeded)
+        );
+        smc.clear_resonance_band();
+
+        smc.set_approach_creep(1, 0.004);
+        assert_eq!(
+            smc.move_to_position(0.001, 3),
+            Err(Error::VelocityExceeded)
+        );
+
+        assert!(!smc.update().unwrap());
+        assert_eq!(recording.borrow().dir_accesses, 0);
+    }
+
+    #[test]
+    fn move_to_position_should_not_cruise_inside_resonance_band() {
+        let MotionFixture {
+            mut stepper,
+            durations,
+            ..
+        } = MotionFixture::new();
+        stepper
+            .driver_mut()
+            .set_resonance_band(0.0005, 0.003_906_25);
+
+        stepper.move_to_position(0.001, 2).wait().unwrap();
+
+        // Direction setup, then a pulse and the rest of the step delay for
+        // each step. At 1/256 steps/tick, each step takes 256 ticks.
+        let ticks: Vec<_> =
+            durations.borrow().iter().map(|d| d.ticks()).collect();
+        assert_eq!(ticks, [1, 1, 255, 1, 255]);
+    }
+
+    #[test]
+    fn restore_should_reestablish_position_and_step_mode() {
+        let MotionFixture { mut stepper, .. } = MotionFixture::new();
+        stepper
+            .driver_mut()
+            .set_step_mode(StepMode256::M16)
+            .unwrap()
+            .wait()
+            .unwrap();
+        stepper.move_to_position(0.001, -5).wait().unwrap();
+
+        let snapshot = stepper.driver().snapshot();
+
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut restored = stepper.release();
+        restored.restore(snapshot).unwrap();
+
+        assert_eq!(restored.current_step(), -5);
+        assert_eq!(restored.current_direction(), Direction::Backward);
+        assert_eq!(restored.step_mode(), Some(StepMode256::M16));
+        assert_eq!(restored.snapshot(), snapshot);
+
+        // Restoring must not start a motion.
+        assert!(!restored.update().unwrap());
+        assert!(recording.borrow().steps.is_empty());
+    }
+
+    #[test]
+    fn step_reverse_should_step_once_and_restore_direction() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+        stepper.move_to_position(0.001, 2).wait().unwrap();
+
+        stepper.driver_mut().step_reverse().unwrap();
+
+        use Direction::*;
+        assert_eq!(
+            recording.borrow().steps,
+            [(Forward, 0), (Forward, 1), (Backward, 2)]
+        );
+        assert_eq!(recording.borrow().direction, Some(Forward));
+        assert_eq!(stepper.driver().current_direction(), Forward);
+        assert_eq!(stepper.driver().current_step(), 1);
+    }
+
+    #[test]
+    fn step_interval_bounds_should_clamp_delays() {
+        let profile = ScriptedProfile::new(&[10.0, 500.0, 5000.0]);
+        let MotionFixture {
+            mut stepper,
+            durations,
+            ..
+        } = MotionFixture::with_profile(profile, Ticks);
+        stepper.driver_mut().set_step_interval_bounds(
+            Nanoseconds::micros(100),
+            Nanoseconds::micros(1000),
+        );
+
+        stepper.move_to_position(0.001, 3).wait().unwrap();
+
+        // Direction setup, then a pulse and the rest of the step delay for
+        // each step.
+        let ticks: Vec<_> =
+            durations.borrow().iter().map(|d| d.ticks()).collect();
+        assert_eq!(ticks, [1, 1, 99, 1, 499, 1, 999]);
+    }
+
+    #[test]
+    fn step_interval_bounds_should_be_swapped_if_inverted() {
+        let profile = ScriptedProfile::new(&[10.0, 5000.0]);
+        let MotionFixture {
+            mut stepper,
+            durations,
+            ..
+        } = MotionFixture::with_profile(profile, Ticks);
+        stepper.driver_mut().set_step_interval_bounds(
+            Nanoseconds::micros(1000),
+            Nanoseconds::micros(100),
+        );
+
+        stepper.move_to_position(0.001, 2).wait().unwrap();
+
+        // The same clamping as with the bounds in the right order.
+        let ticks: Vec<_> =
+            durations.borrow().iter().map(|d| d.ticks()).collect();
+        assert_eq!(ticks, [1, 1, 99, 1, 999]);
+    }
+
+    #[test]
+    fn move_to_index_should_move_to_registered_positions() {
+        let MotionFixture { mut stepper, .. } = MotionFixture::new();
+        let smc = stepper.driver_mut();
+
+        smc.register_position(0, 0).unwrap();
+        smc.register_position(1, 12).unwrap();
+        smc.register_position(2, -7).unwrap();
+
+        for &(index, step) in &[(1, 12), (2, -7), (0, 0)] {
+            smc.move_to_index(0.001, index).unwrap();
+            while smc.update().unwrap() {}
+            assert_eq!(smc.current_step(), step);
+        }
+
+        assert_eq!(
+            smc.move_to_index(0.001, 3),
+            Err(PositionError::InvalidIndex)
+        );
+        assert_eq!(
+            smc.register_position(MAX_POSITIONS, 1),
+            Err(PositionError::InvalidIndex)
+        );
+    }
+
+    #[test]
+    fn assert_arrived_should_report_aborted_motion() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+
+        stepper.move_to_position(0.001, 2).wait().unwrap();
+        assert_eq!(stepper.driver().assert_arrived(), Ok(()));
+
+        {
+            let mut future = stepper.move_to_position(0.001, 5);
+            while recording.borrow().steps.len() < 4 {
+                assert!(future.poll().is_pending());
+            }
+            recording.borrow_mut().fail_steps = true;
+            assert!(future.wait().is_err());
+        }
+
+        let mismatch = stepper.driver().assert_arrived().unwrap_err();
+        assert_eq!(
+            mismatch,
+            PositionMismatch {
+                expected: 5,
+                actual: 4
+            }
+        );
+        assert_eq!(mismatch.discrepancy(), 1);
+    }
+
+    #[test]
+    fn set_accel_time_should_reach_max_velocity_in_that_time() {
+        type Num = fixed::FixedI64<typenum::U32>;
+
+        let mut smc = MotionFixture::with_profile(
+            Trapezoidal::new(Num::from_num(1)),
+            Ticks,
+        )
+        .stepper
+        .release();
+
+        let max_velocity = Num::from_num(1);
+        assert_eq!(
+            smc.set_accel_time(max_velocity, TimerDuration::from_ticks(0)),
+            Err(BusyError::Other(InvalidAccelTime))
+        );
+        smc.set_accel_time(max_velocity, TimerDuration::from_ticks(200))
+            .unwrap();
+
+        // At the maximum velocity, the delay is 1. Add up all delays until
+        // then, to get the time spent accelerating. The profile only
+        // approximates the ideal ramp, so allow for some deviation.
+        let profile = smc.profile_mut();
+        profile.enter_position_mode(max_velocity, 10_000);
+        let mut accel_time = Num::from_num(0);
+        while let Some(delay) = profile.next_delay() {
+            if delay <= 1 {
+                break;
+            }
+            accel_time += delay;
+        }
+
+        let accel_time = accel_time.to_num::<f32>();
+        assert!(
+            (180.0..220.0).contains(&accel_time),
+            "accel_time: {}",
+            accel_time
+        );
+    }
+
+    #[test]
+    fn measure_travel_should_return_steps_between_end_stops() {
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut smc = stepper.release();
+
+        // The motor starts at 0, with end stops at -4 and 6.
+        let stall = || {
+            let position: i32 = recording
+                .borrow()
+                .steps
+                .iter()
+                .map(|&(direction, _)| direction as i32)
+                .sum();
+            position <= -4 || position >= 6
+        };
+
+        let travel = smc.measure_travel(0.001, stall).unwrap();
+
+        assert_eq!(travel, 10);
+        assert_eq!(smc.current_step(), 10);
+        assert_eq!(recording.borrow().steps.len(), 14);
+    }
+
+    #[test]
+    fn return_to_start_should_move_back_to_start_of_last_motion() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+        stepper.define_current_position_as(-3).unwrap();
+
+        stepper.move_to_position(0.001, 7).wait().unwrap();
+
+        let smc = stepper.driver_mut();
+        smc.return_to_start(0.001).unwrap();
+        while smc.update().unwrap() {}
+
+        assert_eq!(smc.current_step(), -3);
+        let recording = recording.borrow();
+        assert_eq!(recording.steps.len(), 20);
+        assert!(recording.steps[10..]
+            .iter()
+            .all(|&(direction, _)| direction == Direction::Backward));
+    }
+
+    #[test]
+    fn oscillate_should_end_at_center() {
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut smc = stepper.release();
+        smc.reset_position(20).unwrap();
+
+        smc.oscillate(0.001, 20, 5, 3).unwrap();
+
+        assert_eq!(smc.current_step(), 20);
+
+        // Out to +5, over to -5, back to +5, then home to the center. The
+        // direction changes between each of them.
+        let recording = recording.borrow();
+        assert_eq!(recording.steps.len(), 6 * 5);
+        let reversals = recording
+            .steps
+            .windows(2)
+            .filter(|pair| pair[0].0 != pair[1].0)
+            .count();
+        assert_eq!(reversals, 3);
+    }
+
+    #[test]
+    fn oscillate_should_reject_amplitude_that_overflows() {
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut smc = stepper.release();
+
+        assert_eq!(
+            smc.oscillate(0.001, i32::MAX - 2, 3, 1),
+            Err(Error::MoveTooLong)
+        );
+        assert_eq!(
+            smc.oscillate(0.001, 0, u32::MAX, 1),
+            Err(Error::MoveTooLong)
+        );
+        assert!(recording.borrow().steps.is_empty());
+    }
+
+    #[test]
+    fn simplified_error_should_only_have_possible_variants() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+        recording.borrow_mut().fail_steps = true;
+
+        let err = stepper.move_to_position(0.001, 1).wait().unwrap_err();
+
+        // No need to handle inaccessible pins or failed time conversions.
+        let failed_step = match err.simplify() {
+            SimpleError::Step(PinError) => true,
+            SimpleError::SetDirection(PinError)
+            | SimpleError::Timer(_)
+            | SimpleError::VelocityExceeded
+            | SimpleError::MoveTooLong => false,
+        };
+        assert!(failed_step);
+    }
+
+    #[test]
+    fn step_counted_should_return_new_position() {
+        let MotionFixture { mut stepper, .. } = MotionFixture::new();
+        stepper.move_to_position(0.001, -2).wait().unwrap();
+
+        let smc = stepper.driver_mut();
+        assert_eq!(smc.step_counted(), Ok(-3));
+        assert_eq!(smc.step_counted(), Ok(-4));
+        assert_eq!(smc.current_step(), -4);
+    }
+
+    #[test]
+    fn manual_steps_should_update_position() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+        let mut timer = MockTimer::new();
+
+        stepper.move_to_position(0.001, 3).wait().unwrap();
+        stepper.step(&mut timer).wait().unwrap();
+        assert_eq!(stepper.driver().current_step(), 4);
+
+        stepper
+            .set_direction(Direction::Backward, &mut timer)
+            .wait()
+            .unwrap();
+        stepper.step(&mut timer).wait().unwrap();
+        stepper.step(&mut timer).wait().unwrap();
+        assert_eq!(stepper.driver().current_step(), 2);
+
+        stepper.move_to_position(0.001, 5).wait().unwrap();
+        assert_eq!(stepper.driver().current_step(), 5);
+
+        // 3 steps, 3 manual steps, then 3 more steps
+        assert_eq!(recording.borrow().steps.len(), 9);
+    }
+
+    #[test]
+    fn arm_move_should_set_direction_but_wait_for_trigger() {
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut smc = stepper.release();
+
+        smc.arm_move(0.001, -3).unwrap();
+        for _ in 0..10 {
+            assert!(smc.update().unwrap());
+        }
+
+        assert_eq!(recording.borrow().direction, Some(Direction::Backward));
+        assert!(recording.borrow().steps.is_empty());
+
+        smc.trigger();
+        while smc.update().unwrap() {}
+
+        assert_eq!(smc.current_step(), -3);
+        assert_eq!(recording.borrow().steps.len(), 3);
+    }
+
+    #[test]
+    fn arm_move_should_not_stay_armed_after_error() {
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut smc = stepper.release();
+        smc.set_velocity_limit(0.001);
+
+        assert_eq!(smc.arm_move(0.002, 3), Err(Error::VelocityExceeded));
+
+        smc.move_to_position(0.001, 3).unwrap();
+        while smc.update().unwrap() {}
+
+        assert_eq!(smc.current_step(), 3);
+        assert_eq!(recording.borrow().steps.len(), 3);
+    }
+
+    #[test]
+    fn on_state_change_should_report_every_transition() {
+        std::thread_local! {
+            static STATES: RefCell<Vec<&'static str>> =
+                const { RefCell::new(Vec::new()) };
+        }
+
+        let MotionFixture { mut stepper, .. } = MotionFixture::new();
+        stepper.driver_mut().on_state_change(Some(|state| {
+            STATES.with(|states| states.borrow_mut().push(state))
+        }));
+
+        stepper.move_to_position(0.001, 2).wait().unwrap();
+
+        STATES.with(|states| {
+            assert_eq!(
+                *states.borrow(),
+                [
+                    "SetDirection",
+                    "Idle",
+                    "Step",
+                    "StepDelay",
+                    "Idle",
+                    "Step",
+                    "StepDelay",
+                    "Idle",
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn approach_creep_should_make_last_steps_slowly() {
+        let MotionFixture {
+            mut stepper,
+            durations,
+            ..
+        } = MotionFixture::new();
+        stepper.driver_mut().set_approach_creep(2, 0.001_953_125);
+
+        stepper.move_to_position(0.003_906_25, 5).wait().unwrap();
+
+        assert_eq!(stepper.driver().current_step(), 5);
+        assert_eq!(stepper.driver().assert_arrived(), Ok(()));
+
+        // 3 steps at 256 ticks per step, then 2 at 512.
+        let ticks: Vec<_> =
+            durations.borrow().iter().map(|d| d.ticks()).collect();
+        assert_eq!(ticks, [1, 1, 255, 1, 255, 1, 255, 1, 1, 511, 1, 511]);
+    }
+
+    #[test]
+    fn max_timing_error_should_reflect_late_steps() {
+        let driver = RecordingDriver::new();
+        let recording = driver.recording();
+        let clock = ClockTimer::new();
+
+        let mut motion_control =
+            SoftwareMotionControl::<_, _, _, _, TIMER_HZ>::new(
+                driver,
+                clock.clone(),
+                Flat::new(),
+                Ticks,
+            );
+        assert_eq!(motion_control.max_timing_error(), None);
+        motion_control.set_timing_recording(true);
+
+        // Undisturbed, only the ticks taken by polling count as error.
+        motion_control.move_to_position(0.003_906_25, 4).unwrap();
+        while motion_control.update().unwrap() {}
+        let error = motion_control.max_timing_error().unwrap().ticks();
+        assert!(error < 10, "error: {}", error);
+
+        // Delay one step by 500 ticks, as if the MCU was busy elsewhere.
+        motion_control.move_to_position(0.003_906_25, 8).unwrap();
+        let mut delayed = false;
+        while motion_control.update().unwrap() {
+            if recording.borrow().steps.len() == 6 && !delayed {
+                clock.advance(500);
+                delayed = true;
+            }
+        }
+        let error = motion_control.max_timing_error().unwrap().ticks();
+        assert!((500..510).contains(&error), "error: {}", error);
+    }
+
+    #[test]
+    fn move_at_velocity_should_rotate_until_stopped() {
+        type Num = fixed::FixedI64<typenum::U32>;
+
+        // Steps to stop from full speed: 0.01^2 / (2 * 0.00001) = 5
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::with_profile(
+            Trapezoidal::new(Num::from_num(0.000_01)),
+            FixedTicks,
+        );
+        let mut smc = stepper.release();
+
+        smc.move_at_velocity(Num::from_num(0.01), Direction::Backward)
+            .unwrap();
+        let mut stopped = false;
+        while smc.update().unwrap() {
+            if recording.borrow().steps.len() == 100 && !stopped {
+                smc.stop();
+                stopped = true;
+            }
+        }
+
+        let steps = recording.borrow().steps.len();
+        assert!((100..=106).contains(&steps), "steps: {}", steps);
+        assert_eq!(smc.current_step(), -(steps as i32));
+        assert!(recording
+            .borrow()
+            .steps
+            .iter()
+            .all(|&(direction, _)| direction == Direction::Backward));
+    }
+
+    #[test]
+    fn move_at_velocity_should_extend_motion_after_every_step() {
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut smc = stepper.release();
+
+        smc.move_at_velocity(0.001, Direction::Forward).unwrap();
+        // Pretend the profile is almost at its end.
+        smc.profile_mut().enter_position_mode(0.001, 2);
+
+        while recording.borrow().steps.len() < 10 {
+            assert!(smc.update().unwrap());
+        }
+
+        smc.stop();
+        while smc.update().unwrap() {}
+        assert_eq!(smc.current_step(), 10);
+    }
+
+    #[test]
+    fn move_at_velocity_should_wrap_around_at_end_of_range() {
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut smc = stepper.release();
+
+        smc.reset_position(i32::MAX - 1).unwrap();
+        smc.move_at_velocity(0.001, Direction::Forward).unwrap();
+        while recording.borrow().steps.len() < 3 {
+            assert!(smc.update().unwrap());
+        }
+
+        smc.stop();
+        while smc.update().unwrap() {}
+        assert_eq!(smc.current_step(), i32::MIN + 1);
+    }
+
+    #[test]
+    fn prepared_move_should_not_set_direction_again() {
+        let MotionFixture {
+            stepper,
+            recording,
+            durations,
+        } = MotionFixture::new();
+        let mut smc = stepper.release();
+
+        smc.prepare_move(-5).unwrap();
+        while smc.update().unwrap() {}
+        assert_eq!(recording.borrow().direction, Some(Direction::Backward));
+        assert_eq!(recording.borrow().steps.len(), 0);
+        assert_eq!(smc.prepare_move(3), Ok(()));
+        while smc.update().unwrap() {}
+
+        let dir_accesses = recording.borrow().dir_accesses;
+        let setup_waits = durations.borrow().len();
+
+        smc.execute_prepared(0.001).unwrap();
+        while smc.update().unwrap() {}
+
+        assert_eq!(smc.current_step(), 3);
+        assert_eq!(recording.borrow().dir_accesses, dir_accesses);
+        // Only pulse length and step delay for every step, no setup time.
+        assert_eq!(durations.borrow().len() - setup_waits, 3 * 2);
+        assert!(recording
+            .borrow()
+            .steps
+            .iter()
+            .all(|&(direction, _)| direction == Direction::Forward));
+    }
+
+    #[test]
+    fn prepared_move_should_set_direction_if_executed_right_away() {
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut smc = stepper.release();
+
+        smc.move_to_position(0.001, 2).unwrap();
+        while smc.update().unwrap() {}
+        let dir_accesses = recording.borrow().dir_accesses;
+
+        smc.prepare_move(-3).unwrap();
+        smc.execute_prepared(0.001).unwrap();
+        while smc.update().unwrap() {}
+
+        assert_eq!(smc.current_step(), -3);
+        assert_eq!(recording.borrow().direction, Some(Direction::Backward));
+        assert_eq!(recording.borrow().dir_accesses, dir_accesses + 1);
+        assert!(recording.borrow().steps[2..]
+            .iter()
+            .all(|&(direction, _)| direction == Direction::Backward));
+    }
+
+    #[test]
+    fn microstep_up_should_only_rescale_once_step_mode_is_set() {
+        let MotionFixture { mut stepper, .. } = MotionFixture::new();
+        stepper.set_motor_spec(MotorSpec::new(200));
+        stepper
+            .driver_mut()
+            .set_step_mode(StepMode256::M4)
+            .unwrap()
+            .wait()
+            .unwrap();
+        stepper.move_to_position(0.001, 3).wait().unwrap();
+
+        let mut future = stepper.driver_mut().microstep_up().unwrap().unwrap();
+        assert!(future.poll().is_pending());
+        drop(future);
+
+        // The future didn't finish, so nothing may have changed.
+        assert_eq!(stepper.driver().current_step(), 3);
+        assert_eq!(stepper.driver().step_mode(), Some(StepMode256::M4));
+        assert_eq!(stepper.microsteps_per_rev(), Some(800));
+
+        stepper
+            .driver_mut()
+            .microstep_up()
+            .unwrap()
+            .unwrap()
+            .wait()
+            .unwrap();
+
+        assert_eq!(stepper.driver().current_step(), 6);
+        assert_eq!(stepper.driver().step_mode(), Some(StepMode256::M8));
+        assert_eq!(stepper.microsteps_per_rev(), Some(1600));
+    }
+
+    #[test]
+    fn microstep_up_should_stop_at_finest_step_mode() {
+        let mut smc = MotionFixture::new().stepper.release();
+        assert!(smc.microstep_up().unwrap().is_none());
+
+        smc.set_step_mode(StepMode256::M256)
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert!(smc.microstep_up().unwrap().is_none());
+
+        smc.set_step_mode(StepMode256::Full)
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert!(smc.microstep_down().unwrap().is_none());
+    }
+
+    #[test]
+    fn emergency_stop_should_stop_without_ramping_down() {
+        type Num = fixed::FixedI64<typenum::U32>;
+
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::with_profile(
+            Trapezoidal::new(Num::from_num(0.000_01)),
+            FixedTicks,
+        );
+        let mut smc = stepper.release();
+        let max_velocity = Num::from_num(0.01);
+
+        smc.move_to_position(max_velocity, 100).unwrap();
+        while recording.borrow().steps.len() < 20 {
+            smc.update().unwrap();
+        }
+        smc.emergency_stop().unwrap();
+
+        assert!(!smc.update().unwrap());
+        assert!(!recording.borrow().step_high);
+        assert_eq!(recording.borrow().steps.len(), 20);
+        // The last pulse was cut short, so it isn't counted.
+        assert_eq!(smc.current_step(), 19);
+
+        // Motion control is usable again afterwards.
+        smc.move_to_position(max_velocity, 0).unwrap();
+        while smc.update().unwrap() {}
+        assert_eq!(smc.current_step(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn export_schedule_csv_should_apply_step_interval_bounds() {
+        let mut smc = MotionFixture::new().stepper.release();
+        smc.set_step_interval_bounds(
+            Nanoseconds::from_ticks(20_000),
+            Nanoseconds::from_ticks(40_000),
+        );
+
+        // At 1/10 steps per tick, every delay would be 10 ticks, or 10 µs.
+        let csv = smc.export_schedule_csv(0.1, 3).unwrap();
+        let intervals: Vec<&str> = csv
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').nth(2).unwrap())
+            .collect();
+        assert_eq!(intervals, ["20000", "20000", "20000"]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn export_schedule_csv_should_leave_velocity_empty_for_zero_interval() {
+        let mut smc = MotionFixture::new().stepper.release();
+
+        let csv = smc.export_schedule_csv(f32::INFINITY, 1).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("0,0,0,"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn export_schedule_csv_should_have_one_row_per_step() {
+        type Num = fixed::FixedI64<typenum::U32>;
+
+        let mut smc = MotionFixture::with_profile(
+            Trapezoidal::new(Num::from_num(0.000_01)),
+            FixedTicks,
+        )
+        .stepper
+        .release();
+
+        let csv = smc.export_schedule_csv(Num::from_num(0.01), 50).unwrap();
+        let mut lines = csv.lines();
+
+        assert_eq!(
+            lines.next(),
+            Some("step_index,cumulative_time_ns,interval_ns,velocity")
+        );
+        let times: Vec<u64> = lines
+            .map(|line| line.split(',').nth(1).unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(times.len(), 50);
+        assert!(times.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn retarget_should_change_target_without_stopping() {
+        type Num = fixed::FixedI64<typenum::U32>;
+
+        // Steps to stop from full speed: 0.01^2 / (2 * 0.00001) = 5
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::with_profile(
+            Trapezoidal::new(Num::from_num(0.000_01)),
+            FixedTicks,
+        );
+        let mut smc = stepper.release();
+        let max_velocity = Num::from_num(0.01);
+
+        smc.move_to_position(max_velocity, 100).unwrap();
+        let mut retargeted = [false; 2];
+        while smc.update().unwrap() {
+            let steps = recording.borrow().steps.len();
+            if steps == 20 && !retargeted[0] {
+                smc.retarget(max_velocity, 60).unwrap();
+                retargeted[0] = true;
+            }
+            if steps == 40 && !retargeted[1] {
+                // Reverse
+                smc.retarget(max_velocity, 10).unwrap();
+                retargeted[1] = true;
+            }
+        }
+
+        assert_eq!(smc.current_step(), 10);
+        assert_eq!(smc.assert_arrived(), Ok(()));
+
+        // The motor ramped down after the reversal, instead of changing
+        // direction right away. It only changed direction once.
+        let directions: Vec<_> = recording
+            .borrow()
+            .steps
+            .iter()
+            .map(|&(direction, _)| direction)
+            .collect();
+        let forward = directions
+            .iter()
+            .take_while(|&&direction| direction == Direction::Forward)
+            .count();
+        assert!(forward > 41, "forward: {}", forward);
+        assert!(directions[forward..]
+            .iter()
+            .all(|&direction| direction == Direction::Backward));
+    }
+
+    #[test]
+    fn set_direction_should_only_update_direction_once_set() {
+        let mut smc = MotionFixture::new().stepper.release();
+        assert_eq!(smc.current_direction(), Direction::Forward);
+
+        drop(smc.set_direction(Direction::Backward).unwrap());
+        assert_eq!(smc.current_direction(), Direction::Forward);
+
+        smc.set_direction(Direction::Backward)
+            .unwrap()
+            .wait()
+            .unwrap();
+        assert_eq!(smc.current_direction(), Direction::Backward);
+    }
+
+    #[test]
+    fn retarget_ahead_should_not_set_direction_again() {
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut smc = stepper.release();
+
+        smc.move_to_position(0.001, 10).unwrap();
+        while smc.update().unwrap() {
+            if recording.borrow().steps.len() == 5 {
+                smc.retarget(0.001, 20).unwrap();
+            }
+        }
+
+        assert_eq!(smc.current_step(), 20);
+        assert_eq!(recording.borrow().dir_accesses, 1);
+    }
+
+    #[test]
+    fn move_to_position_should_reject_moves_longer_than_max() {
+        let mut smc = MotionFixture::new().stepper.release();
+
+        smc.reset_position(-1).unwrap();
+        assert_eq!(
+            smc.move_to_position(0.001, MAX_MOVE_STEPS as i32 - 1),
+            Ok(())
+        );
+
+        smc.reset_position(-2).unwrap();
+        assert_eq!(
+            smc.move_to_position(0.001, MAX_MOVE_STEPS as i32 - 1),
+            Err(Error::MoveTooLong)
+        );
+        assert_eq!(
+            smc.move_to_position(0.001, i32::MIN),
+            Ok(()),
+            "Moving backward from -2 to the minimum is short enough"
+        );
+    }
+
+    #[test]
+    fn configure_should_apply_all_settings() {
+        let MotionFixture {
+            mut stepper,
+            durations,
+            ..
+        } = MotionFixture::new();
+
+        let config = MotionConfig {
+            step_interval_bounds: Some((250_000, 1_000_000)),
+            skip_dir_setup_time: true,
+            velocity_limit: Some(0.01),
+            approach_creep: Some((1, 0.005)),
+            resonance_band: Some((0.002, 0.003)),
+        };
+        stepper.driver_mut().configure(config).unwrap();
+        assert_eq!(stepper.driver().config(), config);
+
+        assert_eq!(
+            stepper.driver_mut().move_to_position(0.02, 2),
+            Err(Error::VelocityExceeded)
+        );
+
+        // The maximum velocity is raised out of the resonance band, to 333
+        // ticks per step, then the creep takes 200 ticks, which is clamped to
+        // the minimum interval. There's no direction setup time.
+        stepper.move_to_position(0.0025, 2).wait().unwrap();
+        let ticks: Vec<_> =
+            durations.borrow().iter().map(|d| d.ticks()).collect();
+        assert_eq!(ticks, [1, 332, 1, 249]);
+
+        // Clearing everything works
+        let cleared = MotionConfig {
+            step_interval_bounds: None,
+            skip_dir_setup_time: false,
+            velocity_limit: None,
+            approach_creep: None,
+            resonance_band: None,
+        };
+        stepper.driver_mut().configure(cleared).unwrap();
+        assert_eq!(stepper.driver().config(), cleared);
+    }
+}
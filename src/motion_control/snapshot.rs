@@ -0,0 +1,31 @@
+use crate::Direction;
+
+/// A snapshot of the state of [`SoftwareMotionControl`]
+///
+/// Can be acquired using [`SoftwareMotionControl::snapshot`] and applied again
+/// using [`SoftwareMotionControl::restore`]. This is useful for persisting the
+/// position of a machine (for example to flash memory), so it can resume after
+/// a loss of power.
+///
+/// Enable the `serde` feature to make this type serializable.
+///
+/// [`SoftwareMotionControl`]: super::SoftwareMotionControl
+/// [`SoftwareMotionControl::snapshot`]: super::SoftwareMotionControl::snapshot
+/// [`SoftwareMotionControl::restore`]: super::SoftwareMotionControl::restore
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MotionState {
+    /// The current step
+    pub current_step: i32,
+
+    /// The direction of the last motion
+    pub current_direction: Direction,
+
+    /// The active step mode, as microsteps per full step
+    ///
+    /// This is `None`, if the wrapped driver doesn't know its step mode. See
+    /// [`ConfiguredMicrosteps`].
+    ///
+    /// [`ConfiguredMicrosteps`]: crate::traits::ConfiguredMicrosteps
+    pub step_mode: Option<u16>,
+}
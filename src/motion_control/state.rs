@@ -3,6 +3,7 @@ use core::task::Poll;
 use embedded_hal::digital::ErrorType;
 use fugit::{
     NanosDurationU32 as Nanoseconds, TimerDurationU32 as TimerDuration,
+    TimerInstantU32 as TimerInstant,
 };
 use fugit_timer::Timer as TimerTrait;
 use ramp_maker::MotionProfile;
@@ -34,6 +35,78 @@ pub enum State<Driver, Timer, Profile: MotionProfile, const TIMER_HZ: u32> {
     Invalid,
 }
 
+impl<Driver, Timer, Profile: MotionProfile, const TIMER_HZ: u32>
+    State<Driver, Timer, Profile, TIMER_HZ>
+{
+    /// Access the driver, regardless of whether a motion is ongoing
+    pub fn driver(&self) -> Option<&Driver> {
+        match self {
+            Self::Idle { driver, .. } | Self::StepDelay { driver, .. } => {
+                Some(driver)
+            }
+            Self::SetDirection(future) => Some(future.driver()),
+            Self::Step { future, .. } => Some(future.driver()),
+            Self::Invalid => None,
+        }
+    }
+}
+
+/// Configuration that affects every update
+#[derive(Clone, Copy)]
+pub struct Config {
+    pub interval_bounds: Option<(Nanoseconds, Nanoseconds)>,
+    pub skip_dir_setup_time: bool,
+    pub armed: bool,
+    pub on_state_change: Option<fn(&'static str)>,
+}
+
+impl Config {
+    pub fn notify(&self, state: &'static str) {
+        if let Some(on_state_change) = self.on_state_change {
+            on_state_change(state);
+        }
+    }
+}
+
+/// Records how much later than commanded steps were made
+pub struct Timing<const TIMER_HZ: u32> {
+    last_step: Option<TimerInstant<TIMER_HZ>>,
+    commanded: Option<TimerDuration<TIMER_HZ>>,
+    pub max_error: TimerDuration<TIMER_HZ>,
+}
+
+impl<const TIMER_HZ: u32> Timing<TIMER_HZ> {
+    pub fn new() -> Self {
+        Self {
+            last_step: None,
+            commanded: None,
+            max_error: TimerDuration::from_ticks(0),
+        }
+    }
+
+    /// Forget the last step, so the time until the next one isn't measured
+    pub fn motion_started(&mut self) {
+        self.last_step = None;
+        self.commanded = None;
+    }
+
+    fn step_started(&mut self, now: TimerInstant<TIMER_HZ>) {
+        if let (Some(last_step), Some(commanded)) =
+            (self.last_step, self.commanded)
+        {
+            if let Some(actual) = now.checked_duration_since(last_step) {
+                if actual > commanded && actual - commanded > self.max_error {
+                    self.max_error = actual - commanded;
+                }
+            }
+        }
+
+        self.last_step = Some(now);
+        self.commanded = None;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn update<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>(
     mut state: State<Driver, Timer, Profile, TIMER_HZ>,
     new_motion: &mut Option<Direction>,
@@ -41,6 +114,8 @@ pub fn update<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>(
     current_step: &mut i32,
     current_direction: &mut Direction,
     convert: &Convert,
+    config: Config,
+    timing: &mut Option<Timing<TIMER_HZ>>,
 ) -> (
     Result<
         bool,
@@ -73,22 +148,40 @@ where
                     //
                     // Let's update the state, but don't return just yet. We
                     // have more stuff to do (polling the future).
-                    state = State::SetDirection(SetDirectionFuture::new(
-                        direction, driver, timer,
-                    ));
+                    let mut future =
+                        SetDirectionFuture::new(direction, driver, timer);
+                    if config.skip_dir_setup_time {
+                        future = future.skip_setup_time();
+                    }
+                    state = State::SetDirection(future);
+                    config.notify("SetDirection");
                     *current_direction = direction;
+                    if let Some(timing) = timing {
+                        timing.motion_started();
+                    }
                     continue;
                 }
 
+                // The motion has been armed, but not triggered yet. Everything
+                // is prepared, so stay idle until the trigger.
+                if config.armed {
+                    return (Ok(true), State::Idle { driver, timer });
+                }
+
                 // No new motion has been started, but we might still have an
                 // ongoing one. Let's ask the motion profile.
                 if let Some(delay) = profile.next_delay() {
                     // There's a motion ongoing. Let's start the next step, but
                     // again, don't return yet. The future needs to be polled.
+                    let mut timer = timer;
+                    if let Some(timing) = timing {
+                        timing.step_started(timer.now());
+                    }
                     state = State::Step {
                         future: StepFuture::new(driver, timer),
                         delay,
                     };
+                    config.notify("Step");
                     continue;
                 }
 
@@ -104,6 +197,7 @@ where
                         // iteration.
                         let (driver, timer) = future.release();
                         state = State::Idle { driver, timer };
+                        config.notify("Idle");
                         continue;
                     }
                     Poll::Ready(Err(err)) => {
@@ -129,25 +223,35 @@ where
                         // A step was made. Now we need to wait out the rest of
                         // the step delay before we can do something else.
 
-                        *current_step += *current_direction as i32;
+                        *current_step = current_step
+                            .wrapping_add(*current_direction as i32);
 
                         let (driver, mut timer) = future.release();
                         let delay_left: TimerDuration<TIMER_HZ> =
                             match delay_left(
                                 delay,
                                 Driver::PULSE_LENGTH,
+                                config.interval_bounds,
                                 convert,
                             ) {
                                 Ok(delay_left) => delay_left,
                                 Err(err) => {
+                                    config.notify("Idle");
                                     return (
                                         Err(Error::TimeConversion(err)),
                                         State::Idle { driver, timer },
-                                    )
+                                    );
                                 }
                             };
 
+                        if let Some(timing) = timing {
+                            let pulse_length: TimerDuration<TIMER_HZ> =
+                                Driver::PULSE_LENGTH.convert();
+                            timing.commanded = Some(delay_left + pulse_length);
+                        }
+
                         if let Err(err) = timer.start(delay_left) {
+                            config.notify("Idle");
                             return (
                                 Err(Error::StepDelay(err)),
                                 State::Idle { driver, timer },
@@ -155,6 +259,7 @@ where
                         }
 
                         state = State::StepDelay { driver, timer };
+                        config.notify("StepDelay");
                         continue;
                     }
                     Poll::Ready(Err(err)) => {
@@ -180,6 +285,7 @@ where
                         // We've waited out the step delay. Return to idle
                         // state, to figure out what's next.
                         state = State::Idle { driver, timer };
+                        config.notify("Idle");
                         continue;
                     }
                     Err(nb::Error::WouldBlock) => {
@@ -212,16 +318,45 @@ where
 fn delay_left<Delay, Convert, const TIMER_HZ: u32>(
     delay: Delay,
     pulse_length: Nanoseconds,
+    interval_bounds: Option<(Nanoseconds, Nanoseconds)>,
     convert: &Convert,
 ) -> Result<TimerDuration<TIMER_HZ>, TimeConversionError<Convert::Error>>
 where
     Convert: DelayToTicks<Delay, TIMER_HZ>,
 {
-    let delay: TimerDuration<TIMER_HZ> = convert
-        .delay_to_ticks(delay)
-        .map_err(|err| TimeConversionError::DelayToTicks(err))?;
+    let delay = step_interval(delay, interval_bounds, convert)?;
     let pulse_length: TimerDuration<TIMER_HZ> = pulse_length.convert();
 
     let delay_left = delay - pulse_length;
     Ok(delay_left)
 }
+
+/// Convert a delay from the motion profile into the full step period
+///
+/// The result is clamped to `interval_bounds`, if those are set.
+pub fn step_interval<Delay, Convert, const TIMER_HZ: u32>(
+    delay: Delay,
+    interval_bounds: Option<(Nanoseconds, Nanoseconds)>,
+    convert: &Convert,
+) -> Result<TimerDuration<TIMER_HZ>, TimeConversionError<Convert::Error>>
+where
+    Convert: DelayToTicks<Delay, TIMER_HZ>,
+{
+    let mut delay: TimerDuration<TIMER_HZ> = convert
+        .delay_to_ticks(delay)
+        .map_err(TimeConversionError::DelayToTicks)?;
+
+    if let Some((min, max)) = interval_bounds {
+        let min: TimerDuration<TIMER_HZ> = min.convert();
+        let max: TimerDuration<TIMER_HZ> = max.convert();
+
+        if delay < min {
+            delay = min;
+        }
+        if delay > max {
+            delay = max;
+        }
+    }
+
+    Ok(delay)
+}
@@ -0,0 +1,113 @@
+use core::{convert::Infallible, task::Poll};
+
+use fugit_timer::Timer as TimerTrait;
+
+use crate::{
+    stepper::{SetStepModeFuture, SignalError},
+    traits::SetStepMode,
+    util::ref_mut::RefMut,
+};
+
+/// The "future" returned by [`SoftwareMotionControl::microstep_up`] and
+/// [`SoftwareMotionControl::microstep_down`]
+///
+/// Sets the new step mode like [`SetStepModeFuture`]. Once that has finished
+/// successfully, the position of `SoftwareMotionControl` is rescaled to the new
+/// step mode. If setting the step mode fails, the position is left as it is.
+///
+/// Please note that this type provides a custom API and does not implement
+/// [`core::future::Future`]. This might change, when using futures for embedded
+/// development becomes more practical.
+///
+/// [`SoftwareMotionControl::microstep_up`]: super::SoftwareMotionControl::microstep_up
+/// [`SoftwareMotionControl::microstep_down`]: super::SoftwareMotionControl::microstep_down
+#[must_use]
+pub struct SwitchStepModeFuture<
+    'r,
+    Driver: SetStepMode,
+    Timer,
+    const TIMER_HZ: u32,
+> {
+    future: SetStepModeFuture<RefMut<'r, Driver>, RefMut<'r, Timer>, TIMER_HZ>,
+    current_step: &'r mut i32,
+    rescale: Option<(u16, u16)>,
+}
+
+impl<'r, Driver, Timer, const TIMER_HZ: u32>
+    SwitchStepModeFuture<'r, Driver, Timer, TIMER_HZ>
+where
+    Driver: SetStepMode,
+    Timer: TimerTrait<TIMER_HZ>,
+{
+    pub(super) fn new(
+        future: SetStepModeFuture<
+            RefMut<'r, Driver>,
+            RefMut<'r, Timer>,
+            TIMER_HZ,
+        >,
+        current_step: &'r mut i32,
+        from: u16,
+        to: u16,
+    ) -> Self {
+        Self {
+            future,
+            current_step,
+            rescale: Some((from, to)),
+        }
+    }
+
+    /// Poll the future
+    ///
+    /// The future must be polled for the operation to make progress. The
+    /// operation won't start, until this method has been called once. Returns
+    /// [`Poll::Pending`], if the operation is not finished yet, or
+    /// [`Poll::Ready`], once it is.
+    ///
+    /// See [`SetStepModeFuture::poll`] for details.
+    pub fn poll(
+        &mut self,
+    ) -> Poll<
+        Result<
+            (),
+            SignalError<
+                Infallible, // only applies to `SetDirection`, `Step`
+                Driver::Error,
+                Timer::Error,
+            >,
+        >,
+    > {
+        let result = self.future.poll();
+
+        if let Poll::Ready(Ok(())) = result {
+            if let Some((from, to)) = self.rescale.take() {
+                let step = i64::from(*self.current_step) * i64::from(to)
+                    / i64::from(from);
+                *self.current_step =
+                    step.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32;
+            }
+        }
+
+        result
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the operation
+    /// has finished.
+    pub fn wait(
+        &mut self,
+    ) -> Result<
+        (),
+        SignalError<
+            Infallible, // only applies to `SetDirection`, `Step`
+            Driver::Error,
+            Timer::Error,
+        >,
+    > {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{traits::SetDirection, Direction};
+
+/// The driver used by [`SoftwareMotionControl::set_direction`]
+///
+/// Forwards to the wrapped driver, and updates the current direction of
+/// `SoftwareMotionControl`, once the DIR signal has actually been set. If the
+/// future returned by [`SoftwareMotionControl::set_direction`] fails or is
+/// dropped before that, the current direction is left as it is.
+///
+/// [`SoftwareMotionControl::set_direction`]: super::SoftwareMotionControl::set_direction
+pub struct TrackDirection<'r, Driver> {
+    pub(super) driver: &'r mut Driver,
+    pub(super) current_direction: &'r mut Direction,
+}
+
+impl<'r, Driver> SetDirection for TrackDirection<'r, Driver>
+where
+    Driver: SetDirection,
+{
+    const SETUP_TIME: Nanoseconds = Driver::SETUP_TIME;
+
+    type Dir = Driver::Dir;
+    type Error = Driver::Error;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        self.driver.dir()
+    }
+
+    fn direction_set(&mut self, direction: Direction) {
+        *self.current_direction = direction;
+        self.driver.direction_set(direction);
+    }
+}
@@ -0,0 +1,54 @@
+//! Types that describe the physical properties of a motor
+//!
+//! See [`MotorSpec`] for more information.
+
+use crate::step_mode::StepMode;
+
+/// The physical properties of a stepper motor
+///
+/// Stepper can't know anything about the motor that is connected to the
+/// driver. If you need to convert between steps and revolutions, you can
+/// provide this information using [`Stepper::set_motor_spec`].
+///
+/// [`Stepper::set_motor_spec`]: crate::Stepper::set_motor_spec
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MotorSpec {
+    /// The number of full steps per revolution
+    ///
+    /// This is 200 for the very common 1.8° motors, and 400 for 0.9° motors.
+    pub full_steps_per_rev: u16,
+}
+
+impl MotorSpec {
+    /// Create a new instance of `MotorSpec`
+    pub const fn new(full_steps_per_rev: u16) -> Self {
+        Self { full_steps_per_rev }
+    }
+
+    /// Returns the number of (micro-)steps per revolution
+    ///
+    /// Takes the step mode that the driver is configured with into account.
+    pub fn microsteps_per_rev(&self, step_mode: impl StepMode) -> u32 {
+        let microsteps_per_step: u16 = step_mode.into();
+        self.full_steps_per_rev as u32 * microsteps_per_step as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::step_mode::{StepMode256, StepMode32};
+
+    use super::MotorSpec;
+
+    #[test]
+    fn microsteps_per_rev_should_take_step_mode_into_account() {
+        let motor_1_8 = MotorSpec::new(200);
+        let motor_0_9 = MotorSpec::new(400);
+
+        assert_eq!(motor_1_8.microsteps_per_rev(StepMode32::Full), 200);
+        assert_eq!(motor_1_8.microsteps_per_rev(StepMode32::M16), 3200);
+        assert_eq!(motor_1_8.microsteps_per_rev(StepMode256::M256), 51_200);
+        assert_eq!(motor_0_9.microsteps_per_rev(StepMode32::M2), 800);
+        assert_eq!(motor_0_9.microsteps_per_rev(StepMode32::M32), 12_800);
+    }
+}
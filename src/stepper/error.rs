@@ -75,3 +75,15 @@ pub enum SignalError<PinUnavailableError, PinError, TimerError> {
     /// An error originated from working with a timer
     Timer(TimerError),
 }
+
+/// An error returned by [`StepFuture::wait_with_budget`]
+///
+/// [`StepFuture::wait_with_budget`]: crate::StepFuture::wait_with_budget
+#[derive(Debug, Eq, PartialEq)]
+pub enum WaitError<T> {
+    /// The operation didn't complete within the poll budget
+    Stalled,
+
+    /// The operation itself failed
+    Operation(T),
+}
@@ -1,29 +1,46 @@
 mod error;
 mod move_to;
+mod move_tracking;
 mod set_direction;
 mod set_step_mode;
 mod step;
+mod step_compare;
+mod wait_for_idle;
+mod with_timer;
 
 pub use self::{
-    error::{Error, SignalError},
+    error::{Error, SignalError, WaitError},
     move_to::MoveToFuture,
+    move_tracking::MoveTrackingFuture,
     set_direction::SetDirectionFuture,
     set_step_mode::SetStepModeFuture,
     step::StepFuture,
+    step_compare::StepCompareFuture,
+    wait_for_idle::WaitForIdleFuture,
+    with_timer::TimerScope,
 };
 
 use core::convert::Infallible;
 
-use embedded_hal::digital::ErrorType;
-use fugit::NanosDurationU32 as Nanoseconds;
+use embedded_hal::digital::{blocking::OutputPin, ErrorType};
+use fugit::{
+    NanosDurationU32 as Nanoseconds, TimerDurationU32 as TimerDuration,
+};
 use fugit_timer::Timer as TimerTrait;
+use ramp_maker::MotionProfile;
 
 use crate::{
+    motion_control::{
+        self, DelayToTicks, ErrorLatch, Event, EventLog, SoftwareMotionControl,
+    },
+    motor::MotorSpec,
+    step_mode::StepMode,
     traits::{
-        EnableDirectionControl, EnableMotionControl, EnableStepControl,
-        EnableStepModeControl, MotionControl, SetDirection, SetStepMode, Step,
+        ConfiguredMicrosteps, EnableDirectionControl, EnableMotionControl,
+        EnableStepControl, EnableStepModeControl, MotionControl, PulseOutput,
+        ReadyStatus, SetDirection, SetStepMode, Step,
     },
-    util::ref_mut::RefMut,
+    util::{ref_mut::RefMut, sync_output::SyncOutput},
     Direction,
 };
 
@@ -101,12 +118,18 @@ use crate::{
 ///
 pub struct Stepper<Driver> {
     driver: Driver,
+    motor_spec: Option<MotorSpec>,
+    skip_dir_setup_time: bool,
 }
 
 impl<Driver> Stepper<Driver> {
     /// Create a new `Stepper` instance from a driver
     pub fn from_driver(driver: Driver) -> Self {
-        Self { driver }
+        Self {
+            driver,
+            motor_spec: None,
+            skip_dir_setup_time: false,
+        }
     }
 
     /// Access a reference to the wrapped driver
@@ -132,6 +155,91 @@ impl<Driver> Stepper<Driver> {
         self.driver
     }
 
+    /// Wrap the driver in something else, keeping all other settings
+    fn map_driver<NewDriver>(
+        self,
+        f: impl FnOnce(Driver) -> NewDriver,
+    ) -> Stepper<NewDriver> {
+        Stepper {
+            driver: f(self.driver),
+            motor_spec: self.motor_spec,
+            skip_dir_setup_time: self.skip_dir_setup_time,
+        }
+    }
+
+    /// Check whether the driver is ready to step
+    ///
+    /// Only available, if the driver has a status output. See
+    /// [`ReadyStatus`].
+    pub fn is_ready(&mut self) -> Result<bool, Driver::Error>
+    where
+        Driver: ReadyStatus,
+    {
+        self.driver.is_ready()
+    }
+
+    /// Provide information about the physical motor
+    ///
+    /// Stepper can't know anything about the motor by itself, but needs to,
+    /// to convert between steps and revolutions. See [`MotorSpec`].
+    pub fn set_motor_spec(&mut self, motor_spec: MotorSpec) {
+        self.motor_spec = Some(motor_spec);
+    }
+
+    /// Access the motor information set with [`Stepper::set_motor_spec`]
+    pub fn motor_spec(&self) -> Option<MotorSpec> {
+        self.motor_spec
+    }
+
+    /// Returns the number of (micro-)steps per revolution
+    ///
+    /// Combines the information from [`Stepper::set_motor_spec`] with the step
+    /// mode the driver reports through [`ConfiguredMicrosteps`]. Returns
+    /// `None`, if either of those is not known.
+    ///
+    /// Drivers that support [`Stepper::set_step_mode`] know the step mode
+    /// once it has been set. Drivers whose step mode is selected in hardware,
+    /// like the [DQ542MA], need to be told about it.
+    ///
+    /// [DQ542MA]: crate::drivers::dq542ma::DQ542MA
+    pub fn microsteps_per_rev(&self) -> Option<u32>
+    where
+        Driver: ConfiguredMicrosteps,
+    {
+        let full_steps_per_rev = self.motor_spec?.full_steps_per_rev as u32;
+        let microsteps_per_step = self.driver.configured_microsteps()? as u32;
+
+        Some(full_steps_per_rev * microsteps_per_step)
+    }
+
+    /// Compute the steps required to move the given linear distance
+    ///
+    /// `mm_per_rev` is the distance the mechanics move per revolution of the
+    /// motor, for example the lead of a lead screw. The magnitude of the result
+    /// is rounded to the nearest step, and the direction follows the sign of
+    /// `mm`.
+    ///
+    /// Returns `None`, if [`Stepper::microsteps_per_rev`] does.
+    pub fn steps_for_distance(
+        &self,
+        mm: f32,
+        mm_per_rev: f32,
+    ) -> Option<(i32, Direction)>
+    where
+        Driver: ConfiguredMicrosteps,
+    {
+        let microsteps_per_rev = self.microsteps_per_rev()?;
+
+        let (distance, direction) = if mm < 0.0 {
+            (-mm, Direction::Backward)
+        } else {
+            (mm, Direction::Forward)
+        };
+        let steps = distance / mm_per_rev * microsteps_per_rev as f32;
+
+        Some(((steps + 0.5) as i32, direction))
+    }
+
     /// Enable microstepping mode control
     ///
     /// Consumes this instance of `Stepper` and returns a new instance that
@@ -163,9 +271,8 @@ impl<Driver> Stepper<Driver> {
         Driver: EnableStepModeControl<Resources>,
         Timer: TimerTrait<TIMER_HZ>,
     {
-        let mut self_ = Stepper {
-            driver: self.driver.enable_step_mode_control(res),
-        };
+        let mut self_ =
+            self.map_driver(|driver| driver.enable_step_mode_control(res));
         self_.set_step_mode(initial, timer).wait()?;
 
         Ok(self_)
@@ -196,6 +303,21 @@ impl<Driver> Stepper<Driver> {
         )
     }
 
+    /// Returns an iterator over the step modes the driver supports
+    ///
+    /// Starts at full steps and ends at the highest supported number of
+    /// microsteps per step. The values can be passed to
+    /// [`Stepper::set_step_mode`].
+    ///
+    /// You might need to call [`Stepper::enable_step_mode_control`] to make
+    /// this method available.
+    pub fn supported_step_modes(&self) -> <Driver::StepMode as StepMode>::Iter
+    where
+        Driver: SetStepMode,
+    {
+        Driver::StepMode::iter()
+    }
+
     /// Enable direction control
     ///
     /// Consumes this instance of `Stepper` and returns a new instance that
@@ -227,9 +349,8 @@ impl<Driver> Stepper<Driver> {
         Driver: EnableDirectionControl<Resources>,
         Timer: TimerTrait<TIMER_HZ>,
     {
-        let mut self_ = Stepper {
-            driver: self.driver.enable_direction_control(res),
-        };
+        let mut self_ =
+            self.map_driver(|driver| driver.enable_direction_control(res));
         self_.set_direction(initial, timer).wait()?;
 
         Ok(self_)
@@ -248,11 +369,34 @@ impl<Driver> Stepper<Driver> {
         Driver: SetDirection,
         Timer: TimerTrait<TIMER_HZ>,
     {
-        SetDirectionFuture::new(
+        let future = SetDirectionFuture::new(
             direction,
             RefMut(&mut self.driver),
             RefMut(timer),
-        )
+        );
+
+        if self.skip_dir_setup_time {
+            return future.skip_setup_time();
+        }
+
+        future
+    }
+
+    /// Define whether [`Stepper::set_direction`] waits for the setup time
+    ///
+    /// By default, setting the direction includes waiting for the driver's
+    /// setup time, before the returned future completes. If `skip` is `true`,
+    /// this wait is skipped. Only do this, if you can guarantee that enough
+    /// time passes between setting the direction and the next step.
+    ///
+    /// This doesn't affect motions started through
+    /// [`Stepper::move_to_position`]. If you're using the software fallback
+    /// for motion control, see
+    /// [`SoftwareMotionControl::set_skip_dir_setup_time`].
+    ///
+    /// [`SoftwareMotionControl::set_skip_dir_setup_time`]: crate::motion_control::SoftwareMotionControl::set_skip_dir_setup_time
+    pub fn set_skip_dir_setup_time(&mut self, skip: bool) {
+        self.skip_dir_setup_time = skip;
     }
 
     /// Enable step control
@@ -276,9 +420,7 @@ impl<Driver> Stepper<Driver> {
     where
         Driver: EnableStepControl<Resources>,
     {
-        Stepper {
-            driver: self.driver.enable_step_control(res),
-        }
+        self.map_driver(|driver| driver.enable_step_control(res))
     }
 
     /// Rotates the motor one (micro-)step in the given direction
@@ -300,6 +442,231 @@ impl<Driver> Stepper<Driver> {
         StepFuture::new(RefMut(&mut self.driver), RefMut(timer))
     }
 
+    /// Rotates the motor one (micro-)step, using a hardware-generated pulse
+    ///
+    /// Works like [`Stepper::step`], except that the STEP pulse is generated
+    /// by `output`, for example a timer's output-compare channel, using the
+    /// driver's pulse length. The driver's STEP pin is not accessed, so the
+    /// pulse length is not affected by software timing jitter.
+    ///
+    /// You might need to call [`Stepper::enable_step_control`] to make this
+    /// method available.
+    pub fn step_compare<'r, Output, const TIMER_HZ: u32>(
+        &'r mut self,
+        output: &'r mut Output,
+    ) -> StepCompareFuture<RefMut<'r, Driver>, RefMut<'r, Output>, TIMER_HZ>
+    where
+        Driver: Step,
+        Output: PulseOutput<TIMER_HZ>,
+    {
+        StepCompareFuture::new(RefMut(&mut self.driver), RefMut(output))
+    }
+
+    /// Lend a timer to this `Stepper` for a series of operations
+    ///
+    /// Calls `f` with a [`TimerScope`], which provides the methods that would
+    /// otherwise require a timer argument ([`Stepper::step`],
+    /// [`Stepper::set_direction`], [`Stepper::set_step_mode`]), using `timer`
+    /// for all of them. The timer is available again, once `f` returns.
+    ///
+    /// Returns whatever `f` returns.
+    pub fn with_timer<Timer, F, R, const TIMER_HZ: u32>(
+        &mut self,
+        timer: &mut Timer,
+        f: F,
+    ) -> R
+    where
+        Timer: TimerTrait<TIMER_HZ>,
+        F: FnOnce(&mut TimerScope<Driver, Timer, TIMER_HZ>) -> R,
+    {
+        f(&mut TimerScope::new(self, timer))
+    }
+
+    /// Make steps as defined by an external trajectory generator
+    ///
+    /// Each item of `steps` defines one step, consisting of the direction of
+    /// the step and the time until the next step can be made. The direction is
+    /// only changed (respecting the driver's setup time), if it differs from
+    /// the one of the previous step. Before the first step, the direction is
+    /// always set.
+    ///
+    /// This makes it possible to use trajectory generators other than the
+    /// motion profiles supported by [`Stepper::enable_motion_control`].
+    ///
+    /// Unlike most other methods, this one blocks until the operation has
+    /// finished.
+    ///
+    /// You might need to call [`Stepper::enable_direction_control`] and
+    /// [`Stepper::enable_step_control`] to make this method available.
+    pub fn drive_from<Timer, const TIMER_HZ: u32>(
+        &mut self,
+        steps: impl IntoIterator<Item = (Direction, Nanoseconds)>,
+        timer: &mut Timer,
+    ) -> Result<
+        (),
+        motion_control::Error<
+            <Driver as SetDirection>::Error,
+            <<Driver as SetDirection>::Dir as ErrorType>::Error,
+            <Driver as Step>::Error,
+            <<Driver as Step>::Step as ErrorType>::Error,
+            Timer::Error,
+            Infallible,
+        >,
+    >
+    where
+        Driver: SetDirection + Step,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        let pulse_length: TimerDuration<TIMER_HZ> =
+            Driver::PULSE_LENGTH.convert();
+        let mut current_direction = None;
+
+        for (direction, delay) in steps {
+            if current_direction != Some(direction) {
+                self.set_direction(direction, timer)
+                    .wait()
+                    .map_err(motion_control::Error::SetDirection)?;
+                current_direction = Some(direction);
+            }
+
+            self.step(timer)
+                .wait()
+                .map_err(motion_control::Error::Step)?;
+
+            let delay: TimerDuration<TIMER_HZ> = delay.convert();
+            if delay > pulse_length {
+                timer
+                    .start(delay - pulse_length)
+                    .map_err(motion_control::Error::StepDelay)?;
+                nb::block!(timer.wait())
+                    .map_err(motion_control::Error::StepDelay)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Make steps at a step rate that changes linearly from start to end
+    ///
+    /// Makes `steps` steps in `direction`. The step rate of the first step is
+    /// `start_hz`, that of the last one is `end_hz`, and the rate changes by
+    /// the same amount from each step to the next. This is meant for finding
+    /// the step rates at which a motor loses steps, for example because of
+    /// resonance. Steps are not counted, so any lost steps need to be detected
+    /// by other means, like an encoder.
+    ///
+    /// Unlike most other methods, this one blocks until the operation has
+    /// finished. See [`Stepper::drive_from`], which this method uses.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `start_hz` or `end_hz` is zero.
+    pub fn frequency_sweep<Timer, const TIMER_HZ: u32>(
+        &mut self,
+        direction: Direction,
+        start_hz: u32,
+        end_hz: u32,
+        steps: u32,
+        timer: &mut Timer,
+    ) -> Result<
+        (),
+        motion_control::Error<
+            <Driver as SetDirection>::Error,
+            <<Driver as SetDirection>::Dir as ErrorType>::Error,
+            <Driver as Step>::Error,
+            <<Driver as Step>::Step as ErrorType>::Error,
+            Timer::Error,
+            Infallible,
+        >,
+    >
+    where
+        Driver: SetDirection + Step,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        assert!(start_hz > 0 && end_hz > 0, "Step rate must not be zero");
+
+        let last = i64::from(steps.saturating_sub(1).max(1));
+        let sweep = (0..steps).map(|i| {
+            let change =
+                (i64::from(end_hz) - i64::from(start_hz)) * i64::from(i) / last;
+            let hz = (i64::from(start_hz) + change) as u32;
+
+            (direction, Nanoseconds::from_ticks(1_000_000_000 / hz))
+        });
+
+        self.drive_from(sweep, timer)
+    }
+
+    /// Make a number of steps at a constant rate, timed by [`Timer::now`]
+    ///
+    /// Makes `steps` steps at `steps_per_second`, in the direction that was
+    /// previously set. Instead of starting the timer and
+    /// waiting for it to expire, this method busy-waits on the timer's current
+    /// time. The step times are computed from the time of the first step, so
+    /// delays in making one step don't accumulate over the whole motion.
+    ///
+    /// The interval between steps is rounded down to whole timer ticks.
+    ///
+    /// This blocks until the last step pulse has ended.
+    ///
+    /// You might need to call [`Stepper::enable_step_control`] to make this
+    /// method available.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `steps_per_second` is zero.
+    ///
+    /// [`Timer::now`]: fugit_timer::Timer::now
+    pub fn run_constant<Timer, const TIMER_HZ: u32>(
+        &mut self,
+        steps_per_second: u32,
+        steps: u32,
+        timer: &mut Timer,
+    ) -> Result<
+        (),
+        SignalError<
+            Driver::Error,
+            <Driver::Step as ErrorType>::Error,
+            Infallible,
+        >,
+    >
+    where
+        Driver: Step,
+        Timer: TimerTrait<TIMER_HZ>,
+    {
+        assert!(steps_per_second > 0, "Step rate must not be zero");
+
+        let step_interval =
+            TimerDuration::<TIMER_HZ>::from_ticks(TIMER_HZ / steps_per_second);
+        let pulse_length: TimerDuration<TIMER_HZ> =
+            Driver::PULSE_LENGTH.convert();
+
+        let mut step_start = timer.now();
+
+        for i in 0..steps {
+            if i > 0 {
+                step_start += step_interval;
+                while timer.now() < step_start {}
+            }
+
+            self.driver
+                .step()
+                .map_err(SignalError::PinUnavailable)?
+                .set_high()
+                .map_err(SignalError::Pin)?;
+
+            while timer.now() < step_start + pulse_length {}
+            self.driver
+                .step()
+                .map_err(SignalError::PinUnavailable)?
+                .set_low()
+                .map_err(SignalError::Pin)?;
+            self.driver.step_made();
+        }
+
+        Ok(())
+    }
+
     /// Returns the step pulse length of the wrapped driver/controller
     ///
     /// The pulse length is also available through the [`Step`] trait. This
@@ -340,9 +707,20 @@ impl<Driver> Stepper<Driver> {
     where
         Driver: EnableMotionControl<Resources, TIMER_HZ>,
     {
-        Stepper {
-            driver: self.driver.enable_motion_control(res),
-        }
+        self.map_driver(|driver| driver.enable_motion_control(res))
+    }
+
+    /// Return the current position of the motor, in steps
+    ///
+    /// Returns an error, if the driver doesn't track its position.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn current_position(&self) -> Result<i32, Driver::Error>
+    where
+        Driver: MotionControl,
+    {
+        self.driver.current_position()
     }
 
     /// Move the motor to the given position
@@ -372,12 +750,162 @@ impl<Driver> Stepper<Driver> {
         MoveToFuture::new(RefMut(&mut self.driver), max_velocity, target_step)
     }
 
+    /// Move the motor by the given number of steps
+    ///
+    /// Works like [`Stepper::move_to_position`], except that `steps` is
+    /// relative to the current position. Negative values move the motor
+    /// backward.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn move_by<'r>(
+        &'r mut self,
+        max_velocity: Driver::Velocity,
+        steps: i32,
+    ) -> MoveToFuture<RefMut<'r, Driver>>
+    where
+        Driver: MotionControl,
+    {
+        MoveToFuture::relative(RefMut(&mut self.driver), max_velocity, steps)
+    }
+
+    /// Move the motor through a series of waypoints
+    ///
+    /// Each waypoint consists of a target step and the maximum velocity to
+    /// move there with. The waypoints are visited in order, and the motor
+    /// comes to a stop at each of them, as if [`Stepper::move_to_position`]
+    /// had been called for each waypoint, waiting for each motion to finish.
+    ///
+    /// Unlike most other methods, this one blocks until the operation has
+    /// finished.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn run_program(
+        &mut self,
+        waypoints: &[(i32, Driver::Velocity)],
+    ) -> Result<(), Driver::Error>
+    where
+        Driver: MotionControl,
+    {
+        for &(target_step, max_velocity) in waypoints {
+            self.move_to_position(max_velocity, target_step).wait()?;
+        }
+
+        Ok(())
+    }
+
+    /// Move the motor towards a target that can change during the motion
+    ///
+    /// Works like [`Stepper::move_to_position`], except that the target is
+    /// queried from `get_target` every time the returned future is polled. If
+    /// the target changes, the ongoing motion is re-targeted using
+    /// [`MotionControl::retarget`], without stopping first. Whether the motion
+    /// continues from its current velocity depends on the driver. This is
+    /// useful for tracking a moving target.
+    ///
+    /// The future completes once the motor has arrived at the most recently
+    /// queried target.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn move_tracking<'r, GetTarget>(
+        &'r mut self,
+        get_target: GetTarget,
+        max_velocity: Driver::Velocity,
+    ) -> MoveTrackingFuture<RefMut<'r, Driver>, GetTarget>
+    where
+        Driver: MotionControl,
+        GetTarget: FnMut() -> i32,
+    {
+        MoveTrackingFuture::new(
+            RefMut(&mut self.driver),
+            get_target,
+            max_velocity,
+        )
+    }
+
+    /// Wait until an ongoing motion has finished
+    ///
+    /// Unlike [`Stepper::move_to_position`], this doesn't start a motion. It
+    /// only keeps an ongoing one going (for example one started by dropping a
+    /// future returned by another method), until it has finished. If no motion
+    /// is ongoing, the returned future completes immediately.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn wait_for_idle(&mut self) -> WaitForIdleFuture<RefMut<'_, Driver>>
+    where
+        Driver: MotionControl,
+    {
+        WaitForIdleFuture::new(RefMut(&mut self.driver))
+    }
+
+    /// Store errors that happen during motions, instead of returning them
+    ///
+    /// Wraps the driver in an [`ErrorLatch`]. Afterwards, errors that happen
+    /// while a motion is being updated are kept, until they are retrieved
+    /// using [`Stepper::take_error`]. This is useful, if motions are updated
+    /// from an interrupt handler that can't handle errors itself.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn latch_errors(self) -> Stepper<ErrorLatch<Driver>>
+    where
+        Driver: MotionControl,
+    {
+        self.map_driver(|driver| ErrorLatch::new(driver))
+    }
+
+    /// Pulse an auxiliary output pin every `every_n_steps` steps
+    ///
+    /// Wraps the driver in a [`SyncOutput`], which sets `pin` high after every
+    /// `every_n_steps`th step, and low again after the following step. This can
+    /// be used to trigger external equipment, like a camera.
+    ///
+    /// This needs to be called before [`Stepper::enable_motion_control`], so
+    /// the steps made by motion control are counted too.
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `every_n_steps` is zero.
+    pub fn enable_sync_output<Pin>(
+        self,
+        pin: Pin,
+        every_n_steps: u32,
+    ) -> Stepper<SyncOutput<Driver, Pin>>
+    where
+        Pin: OutputPin<Error = Infallible>,
+    {
+        self.map_driver(|driver| SyncOutput::new(driver, pin, every_n_steps))
+    }
+
+    /// Record motion events in a log that can be drained later
+    ///
+    /// Wraps the driver in an [`EventLog`] that holds up to `N` events. The
+    /// events can be retrieved using [`Stepper::drain_events`].
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn log_events<const N: usize>(self) -> Stepper<EventLog<Driver, N>>
+    where
+        Driver: MotionControl,
+    {
+        self.map_driver(|driver| EventLog::new(driver))
+    }
+
     /// Reset the position to the given value
     ///
     /// This should never result in a movement, as this method only overwrites
     /// the internal position counter of the driver. However, it might influence
     /// an already ongoing movement.
     ///
+    /// Please note that despite its name, this method does not return the
+    /// motor to position `0` (or any other position). Use
+    /// [`Stepper::move_to_position`] for that. If this is confusing, consider
+    /// using [`Stepper::define_current_position_as`], which does the same
+    /// thing.
+    ///
     /// You might need to call [`Stepper::enable_motion_control`] to make this
     /// method available.
     pub fn reset_position(&mut self, step: i32) -> Result<(), Driver::Error>
@@ -386,4 +914,461 @@ impl<Driver> Stepper<Driver> {
     {
         self.driver.reset_position(step)
     }
+
+    /// Declare that the motor is currently at the given position
+    ///
+    /// This is the same as [`Stepper::reset_position`], under a name that makes
+    /// it clearer what's going on: The motor doesn't move. Only the internal
+    /// position counter of the driver is overwritten, for example after homing.
+    ///
+    /// You might need to call [`Stepper::enable_motion_control`] to make this
+    /// method available.
+    pub fn define_current_position_as(
+        &mut self,
+        step: i32,
+    ) -> Result<(), Driver::Error>
+    where
+        Driver: MotionControl,
+    {
+        self.reset_position(step)
+    }
+}
+
+impl<Driver> Stepper<ErrorLatch<Driver>>
+where
+    Driver: MotionControl,
+{
+    /// Retrieve the error latched during a motion, if any, and clear it
+    ///
+    /// Only available after [`Stepper::latch_errors`] has been called.
+    pub fn take_error(&mut self) -> Option<Driver::Error> {
+        self.driver.take_error()
+    }
+}
+
+impl<Driver, Timer, Profile, Convert, const TIMER_HZ: u32>
+    Stepper<SoftwareMotionControl<Driver, Timer, Profile, Convert, TIMER_HZ>>
+where
+    Driver: SetDirection + Step,
+    Profile: MotionProfile,
+    Timer: TimerTrait<TIMER_HZ>,
+    Convert: DelayToTicks<Profile::Delay, TIMER_HZ>,
+{
+    /// Stop the ongoing motion immediately, without decelerating
+    ///
+    /// Only available for the software fallback for motion control. See
+    /// [`SoftwareMotionControl::emergency_stop`] for details.
+    pub fn emergency_stop(
+        &mut self,
+    ) -> Result<
+        (),
+        motion_control::Error<
+            <Driver as SetDirection>::Error,
+            <<Driver as SetDirection>::Dir as ErrorType>::Error,
+            <Driver as Step>::Error,
+            <<Driver as Step>::Step as ErrorType>::Error,
+            Timer::Error,
+            Convert::Error,
+        >,
+    > {
+        self.driver.emergency_stop()
+    }
+}
+
+impl<Driver, const N: usize> Stepper<EventLog<Driver, N>> {
+    /// Move the logged events into `out`, oldest first
+    ///
+    /// Returns the number of events written. Only available after
+    /// [`Stepper::log_events`] has been called.
+    pub fn drain_events(&mut self, out: &mut [Event]) -> usize {
+        self.driver.drain_events(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+    use std::{cell::RefCell, rc::Rc};
+
+    use embedded_hal::digital::{blocking::OutputPin, ErrorType};
+    use fugit::NanosDurationU32 as Nanoseconds;
+    use ramp_maker::Flat;
+
+    use crate::{
+        motion_control::{self, Event},
+        motor::MotorSpec,
+        step_mode::StepMode256,
+        test_utils::{
+            ClockTimer, MockTimer, MotionFixture, PinError, Recording,
+            RecordingDriver, Ticks,
+        },
+        traits::MotionControl as _,
+        Direction, SignalError, Stepper,
+    };
+
+    #[test]
+    fn define_current_position_as_should_only_change_position() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+        stepper.define_current_position_as(100).unwrap();
+        stepper.move_to_position(0.001, 100).wait().unwrap();
+
+        assert_eq!(stepper.driver().current_step(), 100);
+        assert!(recording.borrow().steps.is_empty());
+    }
+
+    #[test]
+    fn wait_for_idle_should_complete_ongoing_motion() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+
+        // Start the motion, then forget about it.
+        let mut future = stepper.move_to_position(0.001, 5);
+        assert!(future.poll().is_pending());
+        drop(future);
+
+        stepper.wait_for_idle().wait().unwrap();
+
+        assert_eq!(stepper.driver().current_step(), 5);
+        assert_eq!(recording.borrow().steps.len(), 5);
+
+        // Nothing is going on anymore, so this completes right away.
+        assert!(stepper.wait_for_idle().poll().is_ready());
+    }
+
+    #[test]
+    fn latch_errors_should_keep_step_error_until_taken() {
+        let MotionFixture {
+            stepper, recording, ..
+        } = MotionFixture::new();
+        let mut stepper = stepper.latch_errors();
+        recording.borrow_mut().fail_steps = true;
+
+        stepper.move_to_position(0.001, 3).wait().unwrap();
+
+        assert_eq!(
+            stepper.take_error(),
+            Some(motion_control::Error::Step(SignalError::Pin(PinError)))
+        );
+        assert_eq!(stepper.take_error(), None);
+
+        // Once the problem is gone, the motion can be resumed.
+        recording.borrow_mut().fail_steps = false;
+        while stepper.driver_mut().update().unwrap() {}
+
+        assert_eq!(stepper.take_error(), None);
+        assert_eq!(stepper.driver().inner().current_step(), 3);
+    }
+
+    #[test]
+    fn set_direction_should_not_wait_if_setup_time_is_skipped() {
+        let driver = RecordingDriver::new();
+        let recording = driver.recording();
+        let mut timer = MockTimer::new();
+
+        let mut stepper = Stepper::from_driver(driver);
+        stepper.set_skip_dir_setup_time(true);
+
+        assert!(stepper
+            .set_direction(Direction::Forward, &mut timer)
+            .poll()
+            .is_ready());
+
+        assert_eq!(recording.borrow().direction, Some(Direction::Forward));
+        assert!(timer.durations.borrow().is_empty());
+    }
+
+    #[test]
+    fn with_timer_should_lend_timer_for_several_operations() {
+        let driver = RecordingDriver::new();
+        let recording = driver.recording();
+        let mut timer = MockTimer::new();
+
+        let mut stepper = Stepper::from_driver(driver);
+        stepper
+            .with_timer(&mut timer, |scope| {
+                scope.set_direction(Direction::Backward).wait()?;
+                for _ in 0..3 {
+                    scope.step().wait()?;
+                }
+                scope.set_direction(Direction::Forward).wait()?;
+                scope.step().wait()
+            })
+            .unwrap();
+
+        use Direction::*;
+        assert_eq!(
+            recording.borrow().steps,
+            [(Backward, 0), (Backward, 1), (Backward, 2), (Forward, 3)]
+        );
+
+        // The timer is ours again.
+        assert_eq!(timer.durations.borrow().len(), 6);
+    }
+
+    #[test]
+    fn run_program_should_visit_all_waypoints() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+
+        stepper
+            .run_program(&[(4, 0.001), (-2, 0.002), (1, 0.001)])
+            .unwrap();
+
+        assert_eq!(stepper.driver().current_step(), 1);
+        assert_eq!(recording.borrow().steps.len(), 4 + 6 + 3);
+    }
+
+    #[test]
+    fn run_constant_should_space_steps_evenly() {
+        struct Driver(Pin);
+        struct Pin {
+            clock: ClockTimer,
+            rising_edges: Vec<u32>,
+        }
+
+        impl crate::traits::Step for Driver {
+            const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(2_000);
+
+            type Step = Pin;
+            type Error = Infallible;
+
+            fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+                Ok(&mut self.0)
+            }
+        }
+
+        impl ErrorType for Pin {
+            type Error = Infallible;
+        }
+
+        impl OutputPin for Pin {
+            fn set_low(&mut self) -> Result<(), Self::Error> {
+                Ok(())
+            }
+
+            fn set_high(&mut self) -> Result<(), Self::Error> {
+                self.rising_edges.push(self.clock.ticks());
+                Ok(())
+            }
+        }
+
+        let mut timer = ClockTimer::new();
+        let mut stepper = Stepper::from_driver(Driver(Pin {
+            clock: timer.clone(),
+            rising_edges: Vec::new(),
+        }));
+
+        stepper.run_constant(100_000, 5, &mut timer).unwrap();
+
+        let edges = &stepper.driver().0.rising_edges;
+        assert_eq!(edges.len(), 5);
+        assert!(edges.windows(2).all(|pair| pair[1] - pair[0] == 10));
+    }
+
+    #[test]
+    fn drive_from_should_follow_external_trajectory() {
+        use Direction::*;
+
+        let driver = RecordingDriver::new();
+        let recording = driver.recording();
+        let mut timer = MockTimer::new();
+
+        let mut stepper = Stepper::from_driver(driver);
+        stepper
+            .drive_from(
+                [
+                    (Forward, Nanoseconds::micros(10)),
+                    (Forward, Nanoseconds::micros(10)),
+                    (Backward, Nanoseconds::micros(20)),
+                ],
+                &mut timer,
+            )
+            .unwrap();
+
+        assert_eq!(
+            recording.borrow().steps,
+            [(Forward, 0), (Forward, 1), (Backward, 2)]
+        );
+
+        // Direction setup, pulse, and rest of the delay, in ticks
+        let ticks: Vec<_> =
+            timer.durations.borrow().iter().map(|d| d.ticks()).collect();
+        assert_eq!(ticks, [1, 1, 9, 1, 9, 1, 1, 19]);
+    }
+
+    #[test]
+    fn frequency_sweep_should_increase_step_rate_linearly() {
+        let driver = RecordingDriver::new();
+        let recording = driver.recording();
+        let mut timer = MockTimer::new();
+
+        let mut stepper = Stepper::from_driver(driver);
+        stepper
+            .frequency_sweep(Direction::Forward, 1000, 5000, 5, &mut timer)
+            .unwrap();
+
+        assert_eq!(recording.borrow().steps.len(), 5);
+
+        // Direction setup, then pulse and rest of the delay for each step. The
+        // rates are 1, 2, 3, 4, and 5 kHz.
+        let ticks: Vec<_> =
+            timer.durations.borrow().iter().map(|d| d.ticks()).collect();
+        assert_eq!(ticks, [1, 1, 999, 1, 499, 1, 332, 1, 249, 1, 199]);
+    }
+
+    #[test]
+    fn move_by_should_move_relative_to_current_position() {
+        use Direction::*;
+
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+
+        stepper.move_to_position(0.001, 2).wait().unwrap();
+        stepper.move_by(0.001, -3).wait().unwrap();
+        stepper.move_by(0.001, 1).wait().unwrap();
+
+        assert_eq!(stepper.driver().current_step(), 0);
+        let directions: Vec<_> = recording
+            .borrow()
+            .steps
+            .iter()
+            .map(|&(direction, _)| direction)
+            .collect();
+        assert_eq!(
+            directions,
+            [Forward, Forward, Backward, Backward, Backward, Forward]
+        );
+    }
+
+    #[test]
+    fn current_position_should_accumulate_across_motions() {
+        let mut stepper = MotionFixture::new().stepper.latch_errors();
+        assert_eq!(stepper.current_position(), Ok(0));
+
+        stepper.move_to_position(0.001, 3).wait().unwrap();
+        assert_eq!(stepper.current_position(), Ok(3));
+
+        stepper.move_to_position(0.001, -2).wait().unwrap();
+        stepper.move_by(0.001, 4).wait().unwrap();
+        assert_eq!(stepper.current_position(), Ok(2));
+
+        stepper.reset_position(10).unwrap();
+        assert_eq!(stepper.current_position(), Ok(10));
+    }
+
+    #[test]
+    fn steps_for_distance_should_return_magnitude_and_direction() {
+        let mut stepper = Stepper::from_driver(RecordingDriver::new());
+        assert_eq!(stepper.steps_for_distance(1.0, 8.0), None);
+
+        stepper.set_motor_spec(MotorSpec::new(200));
+        stepper
+            .set_step_mode(StepMode256::M8, &mut MockTimer::new())
+            .wait()
+            .unwrap();
+
+        // 1600 steps per revolution, 8 mm per revolution
+        assert_eq!(
+            stepper.steps_for_distance(10.0, 8.0),
+            Some((2000, Direction::Forward))
+        );
+        assert_eq!(
+            stepper.steps_for_distance(-2.5, 8.0),
+            Some((500, Direction::Backward))
+        );
+    }
+
+    #[test]
+    fn log_events_should_record_motion_events() {
+        let mut stepper = MotionFixture::new().stepper.log_events::<4>();
+
+        stepper.reset_position(2).unwrap();
+        stepper.move_to_position(0.001, 5).wait().unwrap();
+
+        let mut events = [Event::MoveFinished; 8];
+        let written = stepper.drain_events(&mut events);
+        assert_eq!(
+            events[..written],
+            [
+                Event::PositionReset { step: 2 },
+                Event::MoveStarted { target_step: 5 },
+                Event::MoveFinished,
+            ]
+        );
+        assert_eq!(stepper.drain_events(&mut events), 0);
+
+        // If the log is full, the oldest events are overwritten.
+        stepper.move_to_position(0.001, 6).wait().unwrap();
+        stepper.move_to_position(0.001, 7).wait().unwrap();
+        stepper.move_to_position(0.001, 8).wait().unwrap();
+        let written = stepper.drain_events(&mut events);
+        assert_eq!(
+            events[..written],
+            [
+                Event::MoveStarted { target_step: 7 },
+                Event::MoveFinished,
+                Event::MoveStarted { target_step: 8 },
+                Event::MoveFinished,
+            ]
+        );
+    }
+
+    #[test]
+    fn sync_output_should_pulse_every_n_steps() {
+        // Records the number of steps made, whenever the pin goes high
+        struct SyncPin {
+            recording: Rc<RefCell<Recording>>,
+            rising_edges: Rc<RefCell<Vec<usize>>>,
+            high: bool,
+        }
+
+        impl ErrorType for SyncPin {
+            type Error = Infallible;
+        }
+
+        impl OutputPin for SyncPin {
+            fn set_low(&mut self) -> Result<(), Self::Error> {
+                self.high = false;
+                Ok(())
+            }
+
+            fn set_high(&mut self) -> Result<(), Self::Error> {
+                if !self.high {
+                    let steps = self.recording.borrow().steps.len();
+                    self.rising_edges.borrow_mut().push(steps);
+                }
+                self.high = true;
+                Ok(())
+            }
+        }
+
+        let driver = RecordingDriver::new();
+        let rising_edges = Rc::new(RefCell::new(Vec::new()));
+        let pin = SyncPin {
+            recording: driver.recording(),
+            rising_edges: rising_edges.clone(),
+            high: false,
+        };
+
+        let mut stepper = Stepper::from_driver(driver)
+            .enable_sync_output(pin, 3)
+            .enable_motion_control((MockTimer::new(), Flat::new(), Ticks));
+        stepper.move_to_position(0.001, 7).wait().unwrap();
+        stepper.move_to_position(0.001, 5).wait().unwrap();
+
+        assert_eq!(*rising_edges.borrow(), [3, 6, 9]);
+    }
 }
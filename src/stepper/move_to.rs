@@ -2,13 +2,15 @@ use core::task::Poll;
 
 use crate::traits::MotionControl;
 
-/// The "future" returned by [`Stepper::move_to_position`]
+/// The "future" returned by [`Stepper::move_to_position`] and
+/// [`Stepper::move_by`]
 ///
 /// Please note that this type provides a custom API and does not implement
 /// [`core::future::Future`]. This might change, when using futures for embedded
 /// development becomes more practical.
 ///
 /// [`Stepper::move_to_position`]: crate::Stepper::move_to_position
+/// [`Stepper::move_by`]: crate::Stepper::move_by
 #[must_use]
 pub struct MoveToFuture<Driver: MotionControl> {
     driver: Driver,
@@ -40,6 +42,27 @@ where
         }
     }
 
+    /// Create new instance of `MoveToFuture` for a relative motion
+    ///
+    /// Like [`MoveToFuture::new`], but moves by `steps`, relative to the
+    /// position at the time the future is first polled. Most users can ignore
+    /// this and just use [`Stepper::move_by`] instead.
+    ///
+    /// [`Stepper::move_by`]: crate::Stepper::move_by
+    pub fn relative(
+        driver: Driver,
+        max_velocity: Driver::Velocity,
+        steps: i32,
+    ) -> Self {
+        Self {
+            driver,
+            state: State::InitialRelative {
+                max_velocity,
+                steps,
+            },
+        }
+    }
+
     /// Poll the future
     ///
     /// The future must be polled for the operation to make progress. The
@@ -61,6 +84,14 @@ where
                 self.state = State::Moving;
                 Poll::Pending
             }
+            State::InitialRelative {
+                max_velocity,
+                steps,
+            } => {
+                self.driver.move_by(max_velocity, steps)?;
+                self.state = State::Moving;
+                Poll::Pending
+            }
             State::Moving => {
                 let still_moving = self.driver.update()?;
                 if still_moving {
@@ -97,6 +128,10 @@ enum State<Velocity> {
         max_velocity: Velocity,
         target_step: i32,
     },
+    InitialRelative {
+        max_velocity: Velocity,
+        steps: i32,
+    },
     Moving,
     Finished,
 }
@@ -0,0 +1,201 @@
+use core::task::Poll;
+
+use crate::traits::MotionControl;
+
+/// The "future" returned by [`Stepper::move_tracking`]
+///
+/// Please note that this type provides a custom API and does not implement
+/// [`core::future::Future`]. This might change, when using futures for embedded
+/// development becomes more practical.
+///
+/// [`Stepper::move_tracking`]: crate::Stepper::move_tracking
+#[must_use]
+pub struct MoveTrackingFuture<Driver: MotionControl, GetTarget> {
+    driver: Driver,
+    get_target: GetTarget,
+    max_velocity: Driver::Velocity,
+    target_step: Option<i32>,
+}
+
+impl<Driver, GetTarget> MoveTrackingFuture<Driver, GetTarget>
+where
+    Driver: MotionControl,
+    GetTarget: FnMut() -> i32,
+{
+    /// Create new instance of `MoveTrackingFuture`
+    ///
+    /// This constructor is public to provide maximum flexibility for
+    /// non-standard use cases. Most users can ignore this and just use
+    /// [`Stepper::move_tracking`] instead.
+    ///
+    /// [`Stepper::move_tracking`]: crate::Stepper::move_tracking
+    pub fn new(
+        driver: Driver,
+        get_target: GetTarget,
+        max_velocity: Driver::Velocity,
+    ) -> Self {
+        Self {
+            driver,
+            get_target,
+            max_velocity,
+            target_step: None,
+        }
+    }
+
+    /// Poll the future
+    ///
+    /// The future must be polled for the operation to make progress. The
+    /// operation won't start, until this method has been called once. Returns
+    /// [`Poll::Pending`], if the operation is not finished yet, or
+    /// [`Poll::Ready`], once it is.
+    ///
+    /// Every call queries the current target. If it has changed since the last
+    /// call, the ongoing motion is re-targeted using
+    /// [`MotionControl::retarget`], without stopping first.
+    ///
+    /// If this method returns [`Poll::Pending`], the user can opt to keep
+    /// calling it at a high frequency (see [`Self::wait`]) until the operation
+    /// completes, or set up an interrupt that fires once the timer finishes
+    /// counting down, and call this method again once it does.
+    pub fn poll(&mut self) -> Poll<Result<(), Driver::Error>> {
+        let target_step = (self.get_target)();
+        match self.target_step {
+            Some(previous) if previous == target_step => {}
+            Some(_) => self.driver.retarget(self.max_velocity, target_step)?,
+            None => self
+                .driver
+                .move_to_position(self.max_velocity, target_step)?,
+        }
+        self.target_step = Some(target_step);
+
+        let still_moving = self.driver.update()?;
+        if still_moving {
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the operation
+    /// has finished.
+    pub fn wait(&mut self) -> Result<(), Driver::Error> {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    /// Drop the future and release the resources that were moved into it
+    pub fn release(self) -> Driver {
+        self.driver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ramp_maker::Trapezoidal;
+
+    use crate::{
+        test_utils::{FixedTicks, MotionFixture},
+        Direction,
+    };
+
+    #[test]
+    fn move_tracking_should_follow_target_that_changes_during_motion() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+
+        let target = {
+            let recording = recording.clone();
+            move || {
+                if recording.borrow().steps.len() < 3 {
+                    10
+                } else {
+                    6
+                }
+            }
+        };
+        stepper.move_tracking(target, 0.001).wait().unwrap();
+
+        assert_eq!(stepper.driver().current_step(), 6);
+        assert!(recording
+            .borrow()
+            .steps
+            .iter()
+            .all(|&(direction, _)| direction == Direction::Forward));
+    }
+
+    #[test]
+    fn move_tracking_should_not_set_direction_again_for_target_ahead() {
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::new();
+
+        // The target moves ahead with every step.
+        let target = {
+            let recording = recording.clone();
+            move || (recording.borrow().steps.len() as i32 + 3).min(20)
+        };
+        stepper.move_tracking(target, 0.001).wait().unwrap();
+
+        assert_eq!(stepper.driver().current_step(), 20);
+        assert_eq!(recording.borrow().dir_accesses, 1);
+    }
+
+    #[test]
+    fn move_tracking_should_ramp_down_before_following_target_behind() {
+        type Num = fixed::FixedI64<typenum::U32>;
+
+        // Steps to stop from full speed: 0.01^2 / (2 * 0.00001) = 5
+        let MotionFixture {
+            mut stepper,
+            recording,
+            ..
+        } = MotionFixture::with_profile(
+            Trapezoidal::new(Num::from_num(0.000_01)),
+            FixedTicks,
+        );
+
+        let target = {
+            let recording = recording.clone();
+            move || {
+                if recording.borrow().steps.len() < 20 {
+                    100
+                } else {
+                    10
+                }
+            }
+        };
+        stepper
+            .move_tracking(target, Num::from_num(0.01))
+            .wait()
+            .unwrap();
+
+        assert_eq!(stepper.driver().current_step(), 10);
+
+        // The motor kept going forward while ramping down, then changed
+        // direction exactly once.
+        let directions: Vec<_> = recording
+            .borrow()
+            .steps
+            .iter()
+            .map(|&(direction, _)| direction)
+            .collect();
+        let forward = directions
+            .iter()
+            .take_while(|&&direction| direction == Direction::Forward)
+            .count();
+        assert!(forward > 21, "forward: {}", forward);
+        assert!(directions[forward..]
+            .iter()
+            .all(|&direction| direction == Direction::Backward));
+    }
+}
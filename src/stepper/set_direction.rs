@@ -21,6 +21,7 @@ pub struct SetDirectionFuture<Driver, Timer, const TIMER_HZ: u32> {
     direction: Direction,
     driver: Driver,
     timer: Timer,
+    skip_setup_time: bool,
     state: State,
 }
 
@@ -42,10 +43,22 @@ where
             direction,
             driver,
             timer,
+            skip_setup_time: false,
             state: State::Initial,
         }
     }
 
+    /// Don't wait for the driver's setup time after setting the direction
+    ///
+    /// The future completes as soon as the DIR signal has been set. Only use
+    /// this, if you can guarantee that enough time passes between setting the
+    /// direction and the next step. Otherwise, the driver might step in the
+    /// wrong direction.
+    pub fn skip_setup_time(mut self) -> Self {
+        self.skip_setup_time = true;
+        self
+    }
+
     /// Poll the future
     ///
     /// The future must be polled for the operation to make progress. The
@@ -75,22 +88,26 @@ where
                     Direction::Forward => self
                         .driver
                         .dir()
-                        .map_err(|err| SignalError::PinUnavailable(err))?
+                        .map_err(SignalError::PinUnavailable)?
                         .set_high()
-                        .map_err(|err| SignalError::Pin(err))?,
+                        .map_err(SignalError::Pin)?,
                     Direction::Backward => self
                         .driver
                         .dir()
-                        .map_err(|err| SignalError::PinUnavailable(err))?
+                        .map_err(SignalError::PinUnavailable)?
                         .set_low()
-                        .map_err(|err| SignalError::Pin(err))?,
+                        .map_err(SignalError::Pin)?,
+                }
+                self.driver.direction_set(self.direction);
+
+                if self.skip_setup_time {
+                    self.state = State::Finished;
+                    return Poll::Ready(Ok(()));
                 }
 
                 let ticks: TimerDuration<TIMER_HZ> =
                     Driver::SETUP_TIME.convert();
-                self.timer
-                    .start(ticks)
-                    .map_err(|err| SignalError::Timer(err))?;
+                self.timer.start(ticks).map_err(SignalError::Timer)?;
 
                 self.state = State::DirectionSet;
                 Poll::Pending
@@ -137,6 +154,15 @@ where
     }
 }
 
+impl<Driver, Timer, const TIMER_HZ: u32>
+    SetDirectionFuture<Driver, Timer, TIMER_HZ>
+{
+    /// Access the driver that was moved into the future
+    pub(crate) fn driver(&self) -> &Driver {
+        &self.driver
+    }
+}
+
 enum State {
     Initial,
     DirectionSet,
@@ -75,33 +75,27 @@ where
             State::Initial => {
                 self.driver
                     .apply_mode_config(self.step_mode)
-                    .map_err(|err| SignalError::Pin(err))?;
+                    .map_err(SignalError::Pin)?;
 
                 let ticks: TimerDuration<TIMER_HZ> =
                     Driver::SETUP_TIME.convert();
 
-                self.timer
-                    .start(ticks)
-                    .map_err(|err| SignalError::Timer(err))?;
+                self.timer.start(ticks).map_err(SignalError::Timer)?;
 
                 self.state = State::ApplyingConfig;
                 Poll::Pending
             }
             State::ApplyingConfig => match self.timer.wait() {
                 Ok(()) => {
-                    self.driver
-                        .enable_driver()
-                        .map_err(|err| SignalError::Pin(err))?;
+                    self.driver.enable_driver().map_err(SignalError::Pin)?;
 
                     let ticks: TimerDuration<TIMER_HZ> =
                         Driver::HOLD_TIME.convert();
 
-                    self.timer
-                        .start(ticks)
-                        .map_err(|err| SignalError::Timer(err))?;
+                    self.timer.start(ticks).map_err(SignalError::Timer)?;
 
                     self.state = State::EnablingDriver;
-                    Poll::Ready(Ok(()))
+                    Poll::Pending
                 }
                 Err(nb::Error::Other(err)) => {
                     self.state = State::Finished;
@@ -111,6 +105,7 @@ where
             },
             State::EnablingDriver => match self.timer.wait() {
                 Ok(()) => {
+                    self.driver.step_mode_set(self.step_mode);
                     self.state = State::Finished;
                     Poll::Ready(Ok(()))
                 }
@@ -120,7 +115,7 @@ where
                 }
                 Err(nb::Error::WouldBlock) => Poll::Pending,
             },
-            // block!(timer.try_wait()).map_err(|err| Error::Timer(err))?;
+            // block!(timer.try_wait()).map_err(Error::Timer)?;
             State::Finished => Poll::Ready(Ok(())),
         }
     }
@@ -158,3 +153,34 @@ enum State {
     EnablingDriver,
     Finished,
 }
+
+#[cfg(test)]
+mod tests {
+    use fugit_timer::Timer as _;
+
+    use crate::{
+        step_mode::StepMode256,
+        test_utils::{MockTimer, RecordingDriver, TIMER_HZ},
+    };
+
+    use super::SetStepModeFuture;
+
+    #[test]
+    fn set_step_mode_should_wait_for_hold_time() {
+        let timer = MockTimer::new();
+        let durations = timer.durations.clone();
+
+        let mut future = SetStepModeFuture::<_, _, TIMER_HZ>::new(
+            StepMode256::M4,
+            RecordingDriver::new(),
+            timer,
+        );
+        future.wait().unwrap();
+
+        // Setup time and hold time have been waited for.
+        assert_eq!(durations.borrow().len(), 2);
+        let (driver, mut timer) = future.release();
+        assert_eq!(timer.wait(), Ok(()));
+        assert_eq!(driver.recording().borrow().step_modes, [StepMode256::M4]);
+    }
+}
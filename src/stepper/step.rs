@@ -7,7 +7,7 @@ use fugit_timer::Timer as TimerTrait;
 
 use crate::traits::Step;
 
-use super::SignalError;
+use super::{SignalError, WaitError};
 
 /// The "future" returned by [`Stepper::step`]
 ///
@@ -71,16 +71,14 @@ where
                 // Start step pulse
                 self.driver
                     .step()
-                    .map_err(|err| SignalError::PinUnavailable(err))?
+                    .map_err(SignalError::PinUnavailable)?
                     .set_high()
-                    .map_err(|err| SignalError::Pin(err))?;
+                    .map_err(SignalError::Pin)?;
 
                 let ticks: TimerDuration<TIMER_HZ> =
                     Driver::PULSE_LENGTH.convert();
 
-                self.timer
-                    .start(ticks)
-                    .map_err(|err| SignalError::Timer(err))?;
+                self.timer.start(ticks).map_err(SignalError::Timer)?;
 
                 self.state = State::PulseStarted;
                 Poll::Pending
@@ -91,9 +89,10 @@ where
                         // End step pulse
                         self.driver
                             .step()
-                            .map_err(|err| SignalError::PinUnavailable(err))?
+                            .map_err(SignalError::PinUnavailable)?
                             .set_low()
-                            .map_err(|err| SignalError::Pin(err))?;
+                            .map_err(SignalError::Pin)?;
+                        self.driver.step_made();
 
                         self.state = State::Finished;
                         Poll::Ready(Ok(()))
@@ -130,14 +129,125 @@ where
         }
     }
 
+    /// Wait until the operation completes, giving up after a number of polls
+    ///
+    /// Like [`Self::wait`], but returns [`WaitError::Stalled`], if the
+    /// operation hasn't completed after calling [`Self::poll`] `max_polls`
+    /// times. This guards against a timer that never fires, which would make
+    /// [`Self::wait`] loop forever.
+    ///
+    /// The future is left as it was after the last poll, so it can be polled
+    /// again or released.
+    pub fn wait_with_budget(
+        &mut self,
+        max_polls: u32,
+    ) -> Result<
+        (),
+        WaitError<
+            SignalError<
+                Driver::Error,
+                <Driver::Step as ErrorType>::Error,
+                Timer::Error,
+            >,
+        >,
+    > {
+        for _ in 0..max_polls {
+            if let Poll::Ready(result) = self.poll() {
+                return result.map_err(WaitError::Operation);
+            }
+        }
+
+        Err(WaitError::Stalled)
+    }
+
     /// Drop the future and release the resources that were moved into it
     pub fn release(self) -> (Driver, Timer) {
         (self.driver, self.timer)
     }
 }
 
+impl<Driver, Timer, const TIMER_HZ: u32> StepFuture<Driver, Timer, TIMER_HZ> {
+    /// Access the driver that was moved into the future
+    pub(crate) fn driver(&self) -> &Driver {
+        &self.driver
+    }
+}
+
 enum State {
     Initial,
     PulseStarted,
     Finished,
 }
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::{TimerDurationU32 as TimerDuration, TimerInstantU32};
+    use fugit_timer::Timer;
+
+    use crate::{
+        test_utils::{MockTimer, RecordingDriver, TIMER_HZ},
+        Direction, Stepper, WaitError,
+    };
+
+    /// A timer that never expires
+    struct StuckTimer;
+
+    impl Timer<TIMER_HZ> for StuckTimer {
+        type Error = Infallible;
+
+        fn now(&mut self) -> TimerInstantU32<TIMER_HZ> {
+            TimerInstantU32::from_ticks(0)
+        }
+
+        fn start(
+            &mut self,
+            _: TimerDuration<TIMER_HZ>,
+        ) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn cancel(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+
+        fn wait(&mut self) -> nb::Result<(), Self::Error> {
+            Err(nb::Error::WouldBlock)
+        }
+    }
+
+    #[test]
+    fn wait_with_budget_should_report_stalled_timer() {
+        let driver = RecordingDriver::new();
+        let recording = driver.recording();
+        let mut stepper = Stepper::from_driver(driver);
+
+        stepper
+            .set_direction(Direction::Forward, &mut MockTimer::new())
+            .wait()
+            .unwrap();
+
+        let result = stepper.step(&mut StuckTimer).wait_with_budget(100);
+
+        assert_eq!(result, Err(WaitError::Stalled));
+        assert!(recording.borrow().step_high);
+    }
+
+    #[test]
+    fn wait_with_budget_should_complete_within_budget() {
+        let driver = RecordingDriver::new();
+        let recording = driver.recording();
+        let mut stepper = Stepper::from_driver(driver);
+
+        stepper
+            .set_direction(Direction::Forward, &mut MockTimer::new())
+            .wait()
+            .unwrap();
+
+        let result = stepper.step(&mut MockTimer::new()).wait_with_budget(3);
+
+        assert_eq!(result, Ok(()));
+        assert_eq!(recording.borrow().steps.len(), 1);
+    }
+}
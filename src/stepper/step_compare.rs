@@ -0,0 +1,164 @@
+use core::task::Poll;
+
+use fugit::TimerDurationU32 as TimerDuration;
+
+use crate::traits::{PulseOutput, Step};
+
+/// The "future" returned by [`Stepper::step_compare`]
+///
+/// Please note that this type provides a custom API and does not implement
+/// [`core::future::Future`]. This might change, when using futures for embedded
+/// development becomes more practical.
+///
+/// [`Stepper::step_compare`]: crate::Stepper::step_compare
+#[must_use]
+pub struct StepCompareFuture<Driver, Output, const TIMER_HZ: u32> {
+    driver: Driver,
+    output: Output,
+    state: State,
+}
+
+impl<Driver, Output, const TIMER_HZ: u32>
+    StepCompareFuture<Driver, Output, TIMER_HZ>
+where
+    Driver: Step,
+    Output: PulseOutput<TIMER_HZ>,
+{
+    /// Create new instance of `StepCompareFuture`
+    ///
+    /// This constructor is public to provide maximum flexibility for
+    /// non-standard use cases. Most users can ignore this and just use
+    /// [`Stepper::step_compare`] instead.
+    ///
+    /// [`Stepper::step_compare`]: crate::Stepper::step_compare
+    pub fn new(driver: Driver, output: Output) -> Self {
+        Self {
+            driver,
+            output,
+            state: State::Initial,
+        }
+    }
+
+    /// Poll the future
+    ///
+    /// The future must be polled for the operation to make progress. The
+    /// operation won't start, until this method has been called once. Returns
+    /// [`Poll::Pending`], if the operation is not finished yet, or
+    /// [`Poll::Ready`], once it is.
+    ///
+    /// If this method returns [`Poll::Pending`], the user can opt to keep
+    /// calling it at a high frequency (see [`Self::wait`]) until the operation
+    /// completes, or set up an interrupt that fires once the pulse has ended,
+    /// and call this method again once it does.
+    pub fn poll(&mut self) -> Poll<Result<(), Output::Error>> {
+        match self.state {
+            State::Initial => {
+                let length: TimerDuration<TIMER_HZ> =
+                    Driver::PULSE_LENGTH.convert();
+                self.output.start_pulse(length)?;
+
+                self.state = State::PulseStarted;
+                Poll::Pending
+            }
+            State::PulseStarted => match self.output.wait() {
+                Ok(()) => {
+                    self.driver.step_made();
+
+                    self.state = State::Finished;
+                    Poll::Ready(Ok(()))
+                }
+                Err(nb::Error::Other(err)) => {
+                    self.state = State::Finished;
+                    Poll::Ready(Err(err))
+                }
+                Err(nb::Error::WouldBlock) => Poll::Pending,
+            },
+            State::Finished => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the operation
+    /// has finished.
+    pub fn wait(&mut self) -> Result<(), Output::Error> {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    /// Drop the future and release the resources that were moved into it
+    pub fn release(self) -> (Driver, Output) {
+        (self.driver, self.output)
+    }
+}
+
+enum State {
+    Initial,
+    PulseStarted,
+    Finished,
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use fugit::TimerDurationU32 as TimerDuration;
+
+    use crate::{
+        test_utils::{RecordingDriver, TIMER_HZ},
+        traits::{PulseOutput, Step as _},
+        Stepper,
+    };
+
+    /// A compare channel that records the pulse lengths it was programmed with
+    struct MockCompare {
+        lengths: Vec<TimerDuration<TIMER_HZ>>,
+        pulse_ongoing: bool,
+    }
+
+    impl PulseOutput<TIMER_HZ> for MockCompare {
+        type Error = Infallible;
+
+        fn start_pulse(
+            &mut self,
+            length: TimerDuration<TIMER_HZ>,
+        ) -> Result<(), Self::Error> {
+            self.lengths.push(length);
+            self.pulse_ongoing = true;
+            Ok(())
+        }
+
+        fn wait(&mut self) -> nb::Result<(), Self::Error> {
+            if self.pulse_ongoing {
+                self.pulse_ongoing = false;
+                return Err(nb::Error::WouldBlock);
+            }
+
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn step_compare_should_program_the_pulse_length() {
+        let driver = RecordingDriver::new();
+        let recording = driver.recording();
+        let mut stepper = Stepper::from_driver(driver);
+        let mut output = MockCompare {
+            lengths: Vec::new(),
+            pulse_ongoing: false,
+        };
+
+        stepper.step_compare(&mut output).wait().unwrap();
+        stepper.step_compare(&mut output).wait().unwrap();
+
+        let pulse_length: TimerDuration<TIMER_HZ> =
+            RecordingDriver::PULSE_LENGTH.convert();
+        assert_eq!(output.lengths, [pulse_length, pulse_length]);
+
+        // The STEP pin was left alone.
+        assert!(recording.borrow().steps.is_empty());
+    }
+}
@@ -0,0 +1,70 @@
+use core::task::Poll;
+
+use crate::traits::MotionControl;
+
+/// The "future" returned by [`Stepper::wait_for_idle`]
+///
+/// Please note that this type provides a custom API and does not implement
+/// [`core::future::Future`]. This might change, when using futures for embedded
+/// development becomes more practical.
+///
+/// [`Stepper::wait_for_idle`]: crate::Stepper::wait_for_idle
+#[must_use]
+pub struct WaitForIdleFuture<Driver> {
+    driver: Driver,
+}
+
+impl<Driver> WaitForIdleFuture<Driver>
+where
+    Driver: MotionControl,
+{
+    /// Create new instance of `WaitForIdleFuture`
+    ///
+    /// This constructor is public to provide maximum flexibility for
+    /// non-standard use cases. Most users can ignore this and just use
+    /// [`Stepper::wait_for_idle`] instead.
+    ///
+    /// [`Stepper::wait_for_idle`]: crate::Stepper::wait_for_idle
+    pub fn new(driver: Driver) -> Self {
+        Self { driver }
+    }
+
+    /// Poll the future
+    ///
+    /// Returns [`Poll::Pending`], if a motion is still ongoing, or
+    /// [`Poll::Ready`], once it has finished. Polling this future keeps the
+    /// ongoing motion going, so it needs to be polled at the same frequency a
+    /// future returned by [`Stepper::move_to_position`] would need to.
+    ///
+    /// If this method returns [`Poll::Pending`], the user can opt to keep
+    /// calling it at a high frequency (see [`Self::wait`]) until the operation
+    /// completes, or set up an interrupt that fires once the timer finishes
+    /// counting down, and call this method again once it does.
+    ///
+    /// [`Stepper::move_to_position`]: crate::Stepper::move_to_position
+    pub fn poll(&mut self) -> Poll<Result<(), Driver::Error>> {
+        let still_moving = self.driver.update()?;
+        if still_moving {
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Wait until the operation completes
+    ///
+    /// This method will call [`Self::poll`] in a busy loop until the operation
+    /// has finished.
+    pub fn wait(&mut self) -> Result<(), Driver::Error> {
+        loop {
+            if let Poll::Ready(result) = self.poll() {
+                return result;
+            }
+        }
+    }
+
+    /// Drop the future and release the resources that were moved into it
+    pub fn release(self) -> Driver {
+        self.driver
+    }
+}
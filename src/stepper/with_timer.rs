@@ -0,0 +1,72 @@
+use fugit_timer::Timer as TimerTrait;
+
+use crate::{
+    traits::{SetDirection, SetStepMode, Step},
+    util::ref_mut::RefMut,
+    Direction, SetDirectionFuture, SetStepModeFuture, StepFuture, Stepper,
+};
+
+/// A [`Stepper`] with a timer that has been lent to it
+///
+/// Provides the methods of [`Stepper`] that require a timer, without the timer
+/// argument. Created by [`Stepper::with_timer`].
+pub struct TimerScope<'r, Driver, Timer, const TIMER_HZ: u32> {
+    stepper: &'r mut Stepper<Driver>,
+    timer: &'r mut Timer,
+}
+
+impl<'r, Driver, Timer, const TIMER_HZ: u32>
+    TimerScope<'r, Driver, Timer, TIMER_HZ>
+where
+    Timer: TimerTrait<TIMER_HZ>,
+{
+    pub(super) fn new(
+        stepper: &'r mut Stepper<Driver>,
+        timer: &'r mut Timer,
+    ) -> Self {
+        Self { stepper, timer }
+    }
+
+    /// Access the `Stepper`, to call methods that don't require a timer
+    pub fn stepper(&mut self) -> &mut Stepper<Driver> {
+        self.stepper
+    }
+
+    /// Sets the microstepping mode
+    ///
+    /// See [`Stepper::set_step_mode`].
+    pub fn set_step_mode(
+        &mut self,
+        step_mode: Driver::StepMode,
+    ) -> SetStepModeFuture<RefMut<'_, Driver>, RefMut<'_, Timer>, TIMER_HZ>
+    where
+        Driver: SetStepMode,
+    {
+        self.stepper.set_step_mode(step_mode, self.timer)
+    }
+
+    /// Set direction for future movements
+    ///
+    /// See [`Stepper::set_direction`].
+    pub fn set_direction(
+        &mut self,
+        direction: Direction,
+    ) -> SetDirectionFuture<RefMut<'_, Driver>, RefMut<'_, Timer>, TIMER_HZ>
+    where
+        Driver: SetDirection,
+    {
+        self.stepper.set_direction(direction, self.timer)
+    }
+
+    /// Rotates the motor one (micro-)step in the given direction
+    ///
+    /// See [`Stepper::step`].
+    pub fn step(
+        &mut self,
+    ) -> StepFuture<RefMut<'_, Driver>, RefMut<'_, Timer>, TIMER_HZ>
+    where
+        Driver: Step,
+    {
+        self.stepper.step(self.timer)
+    }
+}
@@ -0,0 +1,514 @@
+//! Test doubles shared by the unit tests of this crate
+//!
+//! None of this is part of the public API. It only exists to make testing the
+//! higher-level parts of the crate (like [`SoftwareMotionControl`]) easier.
+//!
+//! [`SoftwareMotionControl`]: crate::motion_control::SoftwareMotionControl
+
+use core::convert::Infallible;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    vec::Vec,
+};
+
+use embedded_hal::digital::{
+    blocking::{InputPin, OutputPin},
+    ErrorType,
+};
+use fugit::{
+    NanosDurationU32 as Nanoseconds, TimerDurationU32 as TimerDuration,
+    TimerInstantU32 as TimerInstant,
+};
+use fugit_timer::Timer;
+use ramp_maker::{Flat, MotionProfile};
+
+use crate::{
+    motion_control::{DelayToTicks, SoftwareMotionControl},
+    step_mode::StepMode256,
+    traits::{ConfiguredMicrosteps, SetDirection, SetStepMode, Step},
+    Direction, Stepper,
+};
+
+/// The timer frequency used by all tests
+pub const TIMER_HZ: u32 = 1_000_000;
+
+/// Everything a [`RecordingDriver`] has observed
+#[derive(Debug)]
+pub struct Recording {
+    /// The level of the DIR signal, as last set
+    pub direction: Option<Direction>,
+
+    /// The level of the STEP signal, as last set
+    pub step_high: bool,
+
+    /// All steps made so far, with the direction they were made in
+    ///
+    /// The second element is the index of the step within the recording.
+    pub steps: Vec<(Direction, usize)>,
+
+    /// All step mode changes applied so far
+    pub step_modes: Vec<StepMode256>,
+
+    /// The step mode in effect, as reported by [`SetStepModeFuture`]
+    ///
+    /// [`SetStepModeFuture`]: crate::SetStepModeFuture
+    pub step_mode: Option<StepMode256>,
+
+    /// The number of times the DIR pin was accessed
+    pub dir_accesses: usize,
+
+    /// If `true`, setting the STEP signal fails with [`PinError`]
+    pub fail_steps: bool,
+}
+
+/// A driver that records every step, direction change and step mode change
+///
+/// This makes it possible to assert on the exact sequence of signals a
+/// higher-level component generated, instead of counting calls to mock pins.
+pub struct RecordingDriver {
+    recording: Rc<RefCell<Recording>>,
+    step: RecordingPin,
+    dir: RecordingPin,
+}
+
+impl RecordingDriver {
+    pub fn new() -> Self {
+        let recording = Rc::new(RefCell::new(Recording {
+            direction: None,
+            step_high: false,
+            steps: Vec::new(),
+            step_modes: Vec::new(),
+            step_mode: None,
+            dir_accesses: 0,
+            fail_steps: false,
+        }));
+
+        Self {
+            step: RecordingPin {
+                signal: Signal::Step,
+                recording: recording.clone(),
+            },
+            dir: RecordingPin {
+                signal: Signal::Dir,
+                recording: recording.clone(),
+            },
+            recording,
+        }
+    }
+
+    /// Returns a handle to the recording
+    ///
+    /// The handle stays valid after the driver has been moved into a
+    /// [`Stepper`](crate::Stepper).
+    pub fn recording(&self) -> Rc<RefCell<Recording>> {
+        self.recording.clone()
+    }
+}
+
+impl SetDirection for RecordingDriver {
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(1_000);
+
+    type Dir = RecordingPin;
+    type Error = Infallible;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        self.recording.borrow_mut().dir_accesses += 1;
+        Ok(&mut self.dir)
+    }
+}
+
+impl Step for RecordingDriver {
+    const PULSE_LENGTH: Nanoseconds = Nanoseconds::from_ticks(1_000);
+
+    type Step = RecordingPin;
+    type Error = Infallible;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        Ok(&mut self.step)
+    }
+}
+
+impl SetStepMode for RecordingDriver {
+    const SETUP_TIME: Nanoseconds = Nanoseconds::from_ticks(1_000);
+    const HOLD_TIME: Nanoseconds = Nanoseconds::from_ticks(1_000);
+
+    type Error = Infallible;
+    type StepMode = StepMode256;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        self.recording.borrow_mut().step_modes.push(step_mode);
+        Ok(())
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn step_mode_set(&mut self, step_mode: Self::StepMode) {
+        self.recording.borrow_mut().step_mode = Some(step_mode);
+    }
+}
+
+impl ConfiguredMicrosteps for RecordingDriver {
+    fn configured_microsteps(&self) -> Option<u16> {
+        self.recording.borrow().step_mode.map(Into::into)
+    }
+}
+
+enum Signal {
+    Step,
+    Dir,
+}
+
+/// The error returned by [`RecordingPin`], if it has been told to fail
+#[derive(Debug, Eq, PartialEq)]
+pub struct PinError;
+
+/// An output pin that feeds into a [`Recording`]
+pub struct RecordingPin {
+    signal: Signal,
+    recording: Rc<RefCell<Recording>>,
+}
+
+impl ErrorType for RecordingPin {
+    type Error = PinError;
+}
+
+impl OutputPin for RecordingPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        let mut recording = self.recording.borrow_mut();
+        match self.signal {
+            Signal::Step => recording.step_high = false,
+            Signal::Dir => recording.direction = Some(Direction::Backward),
+        }
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let mut recording = self.recording.borrow_mut();
+        match self.signal {
+            Signal::Step if recording.fail_steps => return Err(PinError),
+            Signal::Step => {
+                let direction = recording
+                    .direction
+                    .expect("Stepped before setting direction");
+                let index = recording.steps.len();
+                recording.steps.push((direction, index));
+                recording.step_high = true;
+            }
+            Signal::Dir => recording.direction = Some(Direction::Forward),
+        }
+        Ok(())
+    }
+}
+
+/// An output pin that remembers its state
+///
+/// Clones share their state, so a clone can be kept around to inspect a pin
+/// after it has been moved into a driver.
+#[derive(Clone)]
+pub struct MockPin {
+    state: Rc<Cell<Option<bool>>>,
+}
+
+impl MockPin {
+    pub fn new() -> Self {
+        Self {
+            state: Rc::new(Cell::new(None)),
+        }
+    }
+
+    /// Returns whether the pin is high, or `None` if it was never set
+    pub fn is_high(&self) -> Option<bool> {
+        self.state.get()
+    }
+}
+
+impl ErrorType for MockPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for MockPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.state.set(Some(false));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.state.set(Some(true));
+        Ok(())
+    }
+}
+
+/// An input pin whose level is controlled by the test
+///
+/// Clones share their level, so a clone can be used to change the level after
+/// the pin has been moved into a driver.
+#[derive(Clone)]
+pub struct MockInputPin {
+    level: Rc<Cell<bool>>,
+}
+
+impl MockInputPin {
+    pub fn new(high: bool) -> Self {
+        Self {
+            level: Rc::new(Cell::new(high)),
+        }
+    }
+
+    /// Change the level the pin reports
+    pub fn set_high(&self, high: bool) {
+        self.level.set(high);
+    }
+}
+
+impl ErrorType for MockInputPin {
+    type Error = Infallible;
+}
+
+impl InputPin for MockInputPin {
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok(self.level.get())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        Ok(!self.level.get())
+    }
+}
+
+/// A timer that remembers every duration it was started with
+///
+/// The timer doesn't actually count anything. It expires on the second call to
+/// `wait` after being started, so users have to poll at least once.
+pub struct MockTimer {
+    pub durations: Rc<RefCell<Vec<TimerDuration<TIMER_HZ>>>>,
+    running: bool,
+}
+
+impl MockTimer {
+    pub fn new() -> Self {
+        Self {
+            durations: Rc::new(RefCell::new(Vec::new())),
+            running: false,
+        }
+    }
+}
+
+impl Timer<TIMER_HZ> for MockTimer {
+    type Error = Infallible;
+
+    fn now(&mut self) -> TimerInstant<TIMER_HZ> {
+        let ticks = self.durations.borrow().iter().map(|d| d.ticks()).sum();
+        TimerInstant::from_ticks(ticks)
+    }
+
+    fn start(
+        &mut self,
+        duration: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), Self::Error> {
+        self.durations.borrow_mut().push(duration);
+        self.running = true;
+        Ok(())
+    }
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        self.running = false;
+        Ok(())
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        if self.running {
+            self.running = false;
+            return Err(nb::Error::WouldBlock);
+        }
+
+        Ok(())
+    }
+}
+
+/// A timer whose clock advances by one tick every time it's read
+///
+/// Clones share their clock, so a clone can be used to read or advance the
+/// time while the original is lent to something else. Since `wait` reads the
+/// clock, a started timer expires after being polled often enough.
+#[derive(Clone)]
+pub struct ClockTimer {
+    ticks: Rc<Cell<u32>>,
+    deadline: Option<u32>,
+}
+
+impl ClockTimer {
+    pub fn new() -> Self {
+        Self {
+            ticks: Rc::new(Cell::new(0)),
+            deadline: None,
+        }
+    }
+
+    /// Returns the current number of ticks, without advancing the clock
+    pub fn ticks(&self) -> u32 {
+        self.ticks.get()
+    }
+
+    /// Advance the clock by the given number of ticks
+    pub fn advance(&self, ticks: u32) {
+        self.ticks.set(self.ticks.get() + ticks);
+    }
+}
+
+impl Timer<TIMER_HZ> for ClockTimer {
+    type Error = Infallible;
+
+    fn now(&mut self) -> TimerInstant<TIMER_HZ> {
+        self.advance(1);
+        TimerInstant::from_ticks(self.ticks())
+    }
+
+    fn start(
+        &mut self,
+        duration: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), Self::Error> {
+        self.deadline = Some(self.ticks() + duration.ticks());
+        Ok(())
+    }
+
+    fn cancel(&mut self) -> Result<(), Self::Error> {
+        self.deadline = None;
+        Ok(())
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        let now = self.now().ticks();
+        match self.deadline {
+            Some(deadline) if now < deadline => Err(nb::Error::WouldBlock),
+            _ => {
+                self.deadline = None;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Converts `f32` delays, which are expected to be in timer ticks already
+pub struct Ticks;
+
+impl DelayToTicks<f32, TIMER_HZ> for Ticks {
+    type Error = Infallible;
+
+    fn delay_to_ticks(
+        &self,
+        delay: f32,
+    ) -> Result<TimerDuration<TIMER_HZ>, Self::Error> {
+        Ok(TimerDuration::from_ticks(delay as u32))
+    }
+}
+
+/// Like [`Ticks`], but for fixed-point delays
+pub struct FixedTicks;
+
+impl DelayToTicks<fixed::FixedI64<typenum::U32>, TIMER_HZ> for FixedTicks {
+    type Error = Infallible;
+
+    fn delay_to_ticks(
+        &self,
+        delay: fixed::FixedI64<typenum::U32>,
+    ) -> Result<TimerDuration<TIMER_HZ>, Self::Error> {
+        Ok(TimerDuration::from_ticks(delay.to_num()))
+    }
+}
+
+/// A motion profile that plays back a fixed list of delays
+///
+/// Ignores the maximum velocity. Once all delays have been used, the last one
+/// is repeated, until the requested number of steps is reached.
+pub struct ScriptedProfile {
+    delays: Vec<f32>,
+    index: usize,
+    steps_left: u32,
+}
+
+impl ScriptedProfile {
+    pub fn new(delays: &[f32]) -> Self {
+        Self {
+            delays: delays.to_vec(),
+            index: 0,
+            steps_left: 0,
+        }
+    }
+}
+
+impl MotionProfile for ScriptedProfile {
+    type Velocity = f32;
+    type Delay = f32;
+
+    fn enter_position_mode(&mut self, _: Self::Velocity, num_steps: u32) {
+        self.steps_left = num_steps;
+    }
+
+    fn next_delay(&mut self) -> Option<Self::Delay> {
+        if self.steps_left == 0 {
+            return None;
+        }
+        self.steps_left -= 1;
+
+        let delay = self.delays[self.index.min(self.delays.len() - 1)];
+        self.index += 1;
+
+        Some(delay)
+    }
+}
+
+/// Software motion control over a [`RecordingDriver`], as most tests need it
+///
+/// Keeps handles to the driver's recording and the timer's durations, so tests
+/// can assert on them after handing the driver and timer to the stepper.
+pub struct MotionFixture<Profile = Flat<f32>, Convert = Ticks>
+where
+    Profile: MotionProfile,
+{
+    pub stepper: Stepper<
+        SoftwareMotionControl<
+            RecordingDriver,
+            MockTimer,
+            Profile,
+            Convert,
+            TIMER_HZ,
+        >,
+    >,
+    pub recording: Rc<RefCell<Recording>>,
+    pub durations: Rc<RefCell<Vec<TimerDuration<TIMER_HZ>>>>,
+}
+
+impl MotionFixture {
+    /// Set up motion control with a flat profile
+    pub fn new() -> Self {
+        Self::with_profile(Flat::new(), Ticks)
+    }
+}
+
+impl<Profile, Convert> MotionFixture<Profile, Convert>
+where
+    Profile: MotionProfile,
+{
+    /// Set up motion control with the given profile and delay conversion
+    pub fn with_profile(profile: Profile, convert: Convert) -> Self {
+        let driver = RecordingDriver::new();
+        let recording = driver.recording();
+        let timer = MockTimer::new();
+        let durations = timer.durations.clone();
+
+        let stepper = Stepper::from_driver(SoftwareMotionControl::new(
+            driver, timer, profile, convert,
+        ));
+
+        Self {
+            stepper,
+            recording,
+            durations,
+        }
+    }
+}
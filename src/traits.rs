@@ -18,17 +18,50 @@
 //! This approach also provides a lot of flexibility for non-standard use cases,
 //! for example if not all driver capabilities are controlled by software.
 //!
+//! If a driver is used in a way that requires a capability it doesn't provide,
+//! the compiler error points out which capability is missing, and how it can
+//! usually be enabled. For example, a driver that has just been constructed
+//! doesn't support setting the direction:
+//!
+//! ``` compile_fail,E0277
+//! use stepper::{drivers::drv8825::DRV8825, traits::SetDirection};
+//!
+//! fn needs_direction(_: impl SetDirection) {}
+//!
+//! needs_direction(DRV8825::new());
+//! ```
+//!
+//! Nor does it provide motion control:
+//!
+//! ``` compile_fail,E0277
+//! use stepper::{drivers::drv8825::DRV8825, traits::MotionControl};
+//!
+//! fn needs_motion_control(_: impl MotionControl) {}
+//!
+//! needs_motion_control(DRV8825::new());
+//! ```
+//!
+//! The messages are defined using `#[diagnostic::on_unimplemented]`, which is
+//! why this crate requires Rust 1.78 or later.
+//!
 //! [`Stepper`]: crate::Stepper
 
 use embedded_hal::digital::blocking::OutputPin;
-use fugit::NanosDurationU32 as Nanoseconds;
+use fugit::{
+    NanosDurationU32 as Nanoseconds, TimerDurationU32 as TimerDuration,
+};
 
-use crate::step_mode::StepMode;
+use crate::{step_mode::StepMode, Direction};
 
 /// Enable microstepping mode control for a driver
 ///
 /// The `Resources` type parameter defines the hardware resources required for
 /// controlling microstepping mode.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't support enabling step mode control using \
+        `{Resources}`",
+    note = "check the driver's documentation for the resources it requires"
+)]
 pub trait EnableStepModeControl<Resources> {
     /// The type of the driver after microstepping mode control has been enabled
     type WithStepModeControl: SetStepMode;
@@ -41,6 +74,10 @@ pub trait EnableStepModeControl<Resources> {
 }
 
 /// Implemented by drivers that support controlling the microstepping mode
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't support setting the step mode",
+    note = "you might need to call `Stepper::enable_step_mode_control` first"
+)]
 pub trait SetStepMode {
     /// The time the mode signals need to be held before re-enabling the driver
     const SETUP_TIME: Nanoseconds;
@@ -67,12 +104,38 @@ pub trait SetStepMode {
 
     /// Re-enable the driver after the mode has been set
     fn enable_driver(&mut self) -> Result<(), Self::Error>;
+
+    /// Notifies the driver that the new step mode is in effect
+    ///
+    /// Called by [`SetStepModeFuture`], once the mode signals have been held
+    /// for [`SetStepMode::HOLD_TIME`]. Drivers that keep track of the step
+    /// mode can override this. The default implementation does nothing.
+    ///
+    /// [`SetStepModeFuture`]: crate::SetStepModeFuture
+    fn step_mode_set(&mut self, _step_mode: Self::StepMode) {}
+}
+
+/// Implemented by drivers that know which step mode they're configured for
+///
+/// Drivers that implement [`SetStepMode`] usually learn about the step mode
+/// through [`SetStepMode::step_mode_set`]. Others, whose step mode is selected
+/// in hardware, need to be told about it.
+pub trait ConfiguredMicrosteps {
+    /// Return the number of microsteps per full step
+    ///
+    /// Returns `None`, if the step mode is not known.
+    fn configured_microsteps(&self) -> Option<u16>;
 }
 
 /// Enable direction control for a driver
 ///
 /// The `Resources` type parameter defines the hardware resources required for
 /// direction control.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't support enabling direction control using \
+        `{Resources}`",
+    note = "check the driver's documentation for the resources it requires"
+)]
 pub trait EnableDirectionControl<Resources> {
     /// The type of the driver after direction control has been enabled
     type WithDirectionControl: SetDirection;
@@ -85,6 +148,10 @@ pub trait EnableDirectionControl<Resources> {
 }
 
 /// Implemented by drivers that support controlling the DIR signal
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't support setting the direction",
+    note = "you might need to call `Stepper::enable_direction_control` first"
+)]
 pub trait SetDirection {
     /// The time that the DIR signal must be held for a change to apply
     const SETUP_TIME: Nanoseconds;
@@ -97,12 +164,26 @@ pub trait SetDirection {
 
     /// Provides access to the DIR pin
     fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error>;
+
+    /// Notifies the driver that the DIR signal has been set
+    ///
+    /// Called by [`SetDirectionFuture`], right after it has set the DIR pin.
+    /// Drivers that keep track of the direction can override this. The default
+    /// implementation does nothing.
+    ///
+    /// [`SetDirectionFuture`]: crate::SetDirectionFuture
+    fn direction_set(&mut self, _direction: Direction) {}
 }
 
 /// Enable step control for a driver
 ///
 /// The `Resources` type parameter defines the hardware resources required for
 /// step control.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't support enabling step control using \
+        `{Resources}`",
+    note = "check the driver's documentation for the resources it requires"
+)]
 pub trait EnableStepControl<Resources> {
     /// The type of the driver after step control has been enabled
     type WithStepControl: Step;
@@ -112,6 +193,10 @@ pub trait EnableStepControl<Resources> {
 }
 
 /// Implemented by drivers that support controlling the STEP signal
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't support stepping",
+    note = "you might need to call `Stepper::enable_step_control` first"
+)]
 pub trait Step {
     /// The minimum length of a STEP pulse
     const PULSE_LENGTH: Nanoseconds;
@@ -124,12 +209,72 @@ pub trait Step {
 
     /// Provides access to the STEP pin
     fn step(&mut self) -> Result<&mut Self::Step, Self::Error>;
+
+    /// Notifies the driver that a step has been made
+    ///
+    /// Called by [`StepFuture`], once it has finished the STEP pulse. Drivers
+    /// that keep track of the position can override this. The default
+    /// implementation does nothing.
+    ///
+    /// [`StepFuture`]: crate::StepFuture
+    fn step_made(&mut self) {}
+}
+
+/// Implemented by drivers that can report whether they are ready to step
+///
+/// Some drivers have a status output that signals whether they are enabled and
+/// free of faults. Drivers that implement this trait provide access to that
+/// output.
+pub trait ReadyStatus {
+    /// The error that can occur while reading the status
+    type Error;
+
+    /// Check whether the driver is ready to step
+    ///
+    /// Returns `false`, if the driver is in standby, disabled, or reports a
+    /// fault.
+    fn is_ready(&mut self) -> Result<bool, Self::Error>;
+}
+
+/// Implemented by timers that can generate a STEP pulse in hardware
+///
+/// On microcontrollers with output-compare channels, a timer can set the STEP
+/// pin when the pulse starts, and clear it on the compare match. This makes
+/// the pulse length independent of how quickly software reacts.
+///
+/// Used by [`StepCompareFuture`], instead of setting the STEP pin from
+/// software.
+///
+/// [`StepCompareFuture`]: crate::StepCompareFuture
+pub trait PulseOutput<const TIMER_HZ: u32> {
+    /// The error that can occur while generating the pulse
+    type Error;
+
+    /// Start a pulse of the given length
+    ///
+    /// Must return right away, without waiting for the pulse to end.
+    fn start_pulse(
+        &mut self,
+        length: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), Self::Error>;
+
+    /// Check whether the pulse has ended
+    ///
+    /// Returns [`nb::Error::WouldBlock`], while the pulse is ongoing.
+    fn wait(&mut self) -> nb::Result<(), Self::Error>;
 }
 
 /// Enable motion control for a driver
 ///
 /// The `Resources` type parameter defines the hardware resources required for
 /// motion control.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't support enabling motion control using \
+        `{Resources}`",
+    note = "the software fallback requires direction and step control, so \
+        you might need to call `Stepper::enable_direction_control` and \
+        `Stepper::enable_step_control` first"
+)]
 pub trait EnableMotionControl<Resources, const TIMER_HZ: u32> {
     /// The type of the driver after motion control has been enabled
     type WithMotionControl: MotionControl;
@@ -144,6 +289,10 @@ pub trait EnableMotionControl<Resources, const TIMER_HZ: u32> {
 /// module, for drivers that implement [SetDirection] and [Step].
 ///
 /// [`motion_control`]: crate::motion_control
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` doesn't support motion control",
+    note = "you might need to call `Stepper::enable_motion_control` first"
+)]
 pub trait MotionControl {
     /// The type used by the driver to represent velocity
     type Velocity: Copy;
@@ -162,6 +311,52 @@ pub trait MotionControl {
         target_step: i32,
     ) -> Result<(), Self::Error>;
 
+    /// Move by the given number of steps, relative to the current position
+    ///
+    /// Negative values of `steps` move backward. Like
+    /// [`MotionControl::move_to_position`], this method must arrange for the
+    /// motion to start, but must not block until it is completed.
+    ///
+    /// The default implementation moves to the position `steps` away from
+    /// [`MotionControl::current_position`], saturating at the bounds of `i32`.
+    /// Drivers that don't track their position should override it.
+    fn move_by(
+        &mut self,
+        max_velocity: Self::Velocity,
+        steps: i32,
+    ) -> Result<(), Self::Error> {
+        let current_position = self.current_position()?;
+        self.move_to_position(
+            max_velocity,
+            current_position.saturating_add(steps),
+        )
+    }
+
+    /// Change the target of an ongoing motion, without stopping first
+    ///
+    /// Works like [`MotionControl::move_to_position`], but continues an
+    /// ongoing motion from its current velocity. If the new target is behind,
+    /// drivers should decelerate to a stop before moving back, instead of
+    /// reversing right away.
+    ///
+    /// The default implementation calls [`MotionControl::move_to_position`].
+    /// Drivers that can do better should override it.
+    fn retarget(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.move_to_position(max_velocity, target_step)
+    }
+
+    /// Return the current position, in steps
+    ///
+    /// This must reflect all steps made so far, across motions, relative to
+    /// the position last set by [`MotionControl::reset_position`].
+    ///
+    /// Drivers that don't track their position should return an error.
+    fn current_position(&self) -> Result<i32, Self::Error>;
+
     /// Reset internal position to the given value
     ///
     /// This method must not start a motion. Its only purpose is to change the
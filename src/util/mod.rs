@@ -1,3 +1,4 @@
 //! Utility module for miscellaneous stuff that the rest of the crate needs
 
 pub mod ref_mut;
+pub mod sync_output;
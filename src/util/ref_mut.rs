@@ -8,7 +8,13 @@ use fugit::{
 };
 use fugit_timer::Timer;
 
-use crate::traits::{MotionControl, SetDirection, SetStepMode, Step};
+use crate::{
+    traits::{
+        ConfiguredMicrosteps, MotionControl, PulseOutput, SetDirection,
+        SetStepMode, Step,
+    },
+    Direction,
+};
 
 /// Generic wrapper around a mutable reference
 ///
@@ -47,6 +53,24 @@ where
     }
 }
 
+impl<'r, T, const TIMER_HZ: u32> PulseOutput<TIMER_HZ> for RefMut<'r, T>
+where
+    T: PulseOutput<TIMER_HZ>,
+{
+    type Error = T::Error;
+
+    fn start_pulse(
+        &mut self,
+        length: TimerDuration<TIMER_HZ>,
+    ) -> Result<(), Self::Error> {
+        self.0.start_pulse(length)
+    }
+
+    fn wait(&mut self) -> nb::Result<(), Self::Error> {
+        self.0.wait()
+    }
+}
+
 impl<'r, T> MotionControl for RefMut<'r, T>
 where
     T: MotionControl,
@@ -62,6 +86,26 @@ where
         self.0.move_to_position(max_velocity, target_step)
     }
 
+    fn move_by(
+        &mut self,
+        max_velocity: Self::Velocity,
+        steps: i32,
+    ) -> Result<(), Self::Error> {
+        self.0.move_by(max_velocity, steps)
+    }
+
+    fn retarget(
+        &mut self,
+        max_velocity: Self::Velocity,
+        target_step: i32,
+    ) -> Result<(), Self::Error> {
+        self.0.retarget(max_velocity, target_step)
+    }
+
+    fn current_position(&self) -> Result<i32, Self::Error> {
+        self.0.current_position()
+    }
+
     fn reset_position(&mut self, step: i32) -> Result<(), Self::Error> {
         self.0.reset_position(step)
     }
@@ -83,6 +127,10 @@ where
     fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
         self.0.dir()
     }
+
+    fn direction_set(&mut self, direction: Direction) {
+        self.0.direction_set(direction)
+    }
 }
 
 impl<'r, T> SetStepMode for RefMut<'r, T>
@@ -105,6 +153,19 @@ where
     fn enable_driver(&mut self) -> Result<(), Self::Error> {
         self.0.enable_driver()
     }
+
+    fn step_mode_set(&mut self, step_mode: Self::StepMode) {
+        self.0.step_mode_set(step_mode)
+    }
+}
+
+impl<'r, T> ConfiguredMicrosteps for RefMut<'r, T>
+where
+    T: ConfiguredMicrosteps,
+{
+    fn configured_microsteps(&self) -> Option<u16> {
+        self.0.configured_microsteps()
+    }
 }
 
 impl<'r, T> Step for RefMut<'r, T>
@@ -119,4 +180,8 @@ where
     fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
         self.0.step()
     }
+
+    fn step_made(&mut self) {
+        self.0.step_made()
+    }
 }
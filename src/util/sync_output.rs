@@ -0,0 +1,151 @@
+//! Pulse an auxiliary output every few steps
+//!
+//! See [`SyncOutput`] for more information.
+
+use core::convert::Infallible;
+
+use embedded_hal::digital::blocking::OutputPin;
+use fugit::NanosDurationU32 as Nanoseconds;
+
+use crate::{
+    traits::{ConfiguredMicrosteps, SetDirection, SetStepMode, Step},
+    Direction,
+};
+
+/// Wraps a driver and pulses an auxiliary pin every `N` steps
+///
+/// This is useful for synchronizing external equipment with the motion, for
+/// example to trigger a camera at regular intervals. Once the `N`th step has
+/// been made, the pin is set high. It is set low again, after the next step.
+/// Steps in both directions are counted.
+///
+/// The pin is set from [`Step::step_made`], which can't return errors, so
+/// only pins that can't fail are supported.
+///
+/// You can use `SyncOutput` directly, but it is designed to be used through
+/// [`Stepper::enable_sync_output`].
+///
+/// [`Stepper::enable_sync_output`]: crate::Stepper::enable_sync_output
+pub struct SyncOutput<Driver, Pin> {
+    driver: Driver,
+    pin: Pin,
+    every_n_steps: u32,
+    steps: u32,
+}
+
+impl<Driver, Pin> SyncOutput<Driver, Pin>
+where
+    Pin: OutputPin<Error = Infallible>,
+{
+    /// Create a new instance of `SyncOutput`
+    ///
+    /// # Panics
+    ///
+    /// Panics, if `every_n_steps` is zero.
+    pub fn new(driver: Driver, pin: Pin, every_n_steps: u32) -> Self {
+        assert!(every_n_steps > 0, "Can't pulse every zero steps");
+
+        Self {
+            driver,
+            pin,
+            every_n_steps,
+            steps: 0,
+        }
+    }
+
+    /// Access a reference to the wrapped driver
+    pub fn inner(&self) -> &Driver {
+        &self.driver
+    }
+
+    /// Access a mutable reference to the wrapped driver
+    pub fn inner_mut(&mut self) -> &mut Driver {
+        &mut self.driver
+    }
+
+    /// Release the wrapped driver and the auxiliary pin
+    pub fn release(self) -> (Driver, Pin) {
+        (self.driver, self.pin)
+    }
+}
+
+impl<Driver, Pin> SetDirection for SyncOutput<Driver, Pin>
+where
+    Driver: SetDirection,
+{
+    const SETUP_TIME: Nanoseconds = Driver::SETUP_TIME;
+
+    type Dir = Driver::Dir;
+    type Error = Driver::Error;
+
+    fn dir(&mut self) -> Result<&mut Self::Dir, Self::Error> {
+        self.driver.dir()
+    }
+
+    fn direction_set(&mut self, direction: Direction) {
+        self.driver.direction_set(direction)
+    }
+}
+
+impl<Driver, Pin> SetStepMode for SyncOutput<Driver, Pin>
+where
+    Driver: SetStepMode,
+{
+    const SETUP_TIME: Nanoseconds = Driver::SETUP_TIME;
+    const HOLD_TIME: Nanoseconds = Driver::HOLD_TIME;
+
+    type Error = Driver::Error;
+    type StepMode = Driver::StepMode;
+
+    fn apply_mode_config(
+        &mut self,
+        step_mode: Self::StepMode,
+    ) -> Result<(), Self::Error> {
+        self.driver.apply_mode_config(step_mode)
+    }
+
+    fn enable_driver(&mut self) -> Result<(), Self::Error> {
+        self.driver.enable_driver()
+    }
+
+    fn step_mode_set(&mut self, step_mode: Self::StepMode) {
+        self.driver.step_mode_set(step_mode)
+    }
+}
+
+impl<Driver, Pin> ConfiguredMicrosteps for SyncOutput<Driver, Pin>
+where
+    Driver: ConfiguredMicrosteps,
+{
+    fn configured_microsteps(&self) -> Option<u16> {
+        self.driver.configured_microsteps()
+    }
+}
+
+impl<Driver, Pin> Step for SyncOutput<Driver, Pin>
+where
+    Driver: Step,
+    Pin: OutputPin<Error = Infallible>,
+{
+    const PULSE_LENGTH: Nanoseconds = Driver::PULSE_LENGTH;
+
+    type Step = Driver::Step;
+    type Error = Driver::Error;
+
+    fn step(&mut self) -> Result<&mut Self::Step, Self::Error> {
+        self.driver.step()
+    }
+
+    fn step_made(&mut self) {
+        self.driver.step_made();
+
+        self.steps += 1;
+        let result = if self.steps == self.every_n_steps {
+            self.steps = 0;
+            self.pin.set_high()
+        } else {
+            self.pin.set_low()
+        };
+        result.unwrap_or_else(|err| match err {})
+    }
+}